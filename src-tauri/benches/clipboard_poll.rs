@@ -0,0 +1,69 @@
+// 剪贴板轮询开销：每次选区变化（或无事件通知时每秒）都会读取内容并计算指纹，
+// 内容很大时这部分开销直接决定了监听线程的 CPU 占用
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use tauri_app_lib::clipboard::{ClipboardBackend, ClipboardManager, ContentType, MemoryBackend};
+
+const SIZES: &[usize] = &[1024, 64 * 1024, 1024 * 1024, 16 * 1024 * 1024];
+
+// 由多行普通文本组成的内容，避免被检测为令牌或卡号
+fn text_of(size: usize) -> Vec<u8> {
+    let line = b"The quick brown fox jumps over the lazy dog. ";
+    line.iter().copied().cycle().take(size).collect()
+}
+
+// 已经看到过 seen 的管理器，之后剪贴板被改写为 next，下一次检查需要读取内容
+fn primed(seen: &[u8], next: &[u8]) -> (ClipboardManager, MemoryBackend) {
+    let backend = MemoryBackend::new();
+    let manager = ClipboardManager::with_backend(Box::new(backend.clone()));
+    backend.set(ContentType::Text, seen).unwrap();
+    manager.check_for_changes().unwrap();
+    backend.set(ContentType::Text, next).unwrap();
+    (manager, backend)
+}
+
+fn poll(c: &mut Criterion) {
+    let mut group = c.benchmark_group("poll");
+    for &size in SIZES {
+        let text = text_of(size);
+        group.throughput(Throughput::Bytes(size as u64));
+
+        // 后端的变化标记未变：不读取内容
+        group.bench_with_input(BenchmarkId::new("unchanged", size), &text, |b, text| {
+            let backend = MemoryBackend::new();
+            let manager = ClipboardManager::with_backend(Box::new(backend.clone()));
+            backend.set(ContentType::Text, text).unwrap();
+            manager.check_for_changes().unwrap();
+            b.iter(|| manager.check_for_changes().unwrap());
+        });
+
+        // 变化标记改变但内容相同（例如其他程序重新设置了同样的内容）：读取并计算指纹
+        group.bench_with_input(BenchmarkId::new("same_content", size), &text, |b, text| {
+            b.iter_batched(
+                || primed(text, text),
+                |(manager, backend)| {
+                    manager.check_for_changes().unwrap();
+                    (manager, backend)
+                },
+                BatchSize::PerIteration,
+            );
+        });
+
+        // 内容确实变化：还要转为文本并检测敏感内容和内容类型
+        let mut other = text.clone();
+        other[0] = b'#';
+        group.bench_with_input(BenchmarkId::new("new_content", size), &text, |b, text| {
+            b.iter_batched(
+                || primed(text, &other),
+                |(manager, backend)| {
+                    manager.check_for_changes().unwrap();
+                    (manager, backend)
+                },
+                BatchSize::PerIteration,
+            );
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, poll);
+criterion_main!(benches);
@@ -0,0 +1,895 @@
+// Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
+mod hotkeys;
+mod notify;
+mod tray;
+
+use crate::clipboard::{ClipboardContent, ClipboardManager};
+use crate::diagnostics::DiagnosticsSource;
+use crate::error::PastoError;
+use crate::history::{HistoryEntry, HistoryPage, HistoryStore, RetentionPolicy};
+use crate::logging::LogLevel;
+use crate::primary::{PrimarySyncConfig, PrimarySyncMode};
+use crate::sensitive::{PendingConfirmation, SensitiveConfig, SensitiveKind};
+use crate::settings::{Settings, SettingsStore};
+use crate::{ble, content_kind, diagnostics, history, hlc, logging, sync, transform, watcher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tracing::{debug, error, info, warn};
+use transform::{Direction, TransformConfig};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tauri_plugin_shell::process::CommandChild;
+#[cfg(target_os = "macos")]
+use tauri_plugin_shell::ShellExt;
+
+// 广播子进程（仅 macOS 使用），进程退出后为 None
+type AdvState = Arc<Mutex<Option<Advertiser>>>;
+type PendingSyncState = Arc<Mutex<PendingConfirmation>>;
+// 暂停时既不发送也不接收，不影响本机历史记录
+type SyncPausedState = Arc<AtomicBool>;
+
+// 请求前端确认是否同步敏感内容
+#[derive(Clone, serde::Serialize)]
+struct SensitiveSyncRequest {
+    id: u64,
+    kind: SensitiveKind,
+}
+
+// 结束广播进程后等待其退出的最长时间
+const ADVERTISER_EXIT_TIMEOUT: Duration = Duration::from_secs(2);
+
+// 广播进程及其退出通知，watch_advertiser 收到 Terminated 后发送
+struct Advertiser {
+    child: CommandChild,
+    exited: std::sync::mpsc::Receiver<()>,
+}
+
+// 应用配置目录中的设置文件名
+const SETTINGS_FILE_NAME: &str = "settings.json";
+
+// 历史记录每页默认条数
+const DEFAULT_HISTORY_PAGE_SIZE: u64 = 50;
+// 历史记录定期清理的间隔
+const HISTORY_COMPACTION_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+#[tauri::command]
+fn greet(name: &str) -> String {
+    format!("Hello, {}! You've been greeted from Rust!", name)
+}
+
+#[tauri::command]
+fn get_clipboard_text(clipboard_manager: tauri::State<Arc<Mutex<ClipboardManager>>>) -> Result<String, PastoError> {
+    match clipboard_manager.lock() {
+        Ok(manager) => manager.get_text(),
+        Err(e) => Err(format!("Failed to lock clipboard manager: {}", e).into()),
+    }
+}
+
+#[tauri::command]
+async fn scan_ble_devices(app: tauri::AppHandle) -> Result<Vec<ble::BleDevice>, PastoError> {
+    let settings = ble_settings(&app)?;
+    ble::scan_ble_devices_once(
+        Duration::from_secs(settings.scan.scan_secs),
+        settings.adapter.as_deref(),
+    )
+    .await
+}
+
+// 读取设置并确认 BLE 传输已启用
+fn ble_settings(app: &tauri::AppHandle) -> Result<Settings, PastoError> {
+    let settings = app.state::<Arc<SettingsStore>>().get()?;
+    if !settings.transports.ble {
+        return Err("BLE transport is disabled in settings".into());
+    }
+    Ok(settings)
+}
+
+// 启动 BLE 广播（支持 macOS 和 Windows）
+#[tauri::command]
+#[tracing::instrument(name = "advertise", skip_all)]
+// 广播子进程只在 macOS 上使用
+#[cfg_attr(not(target_os = "macos"), allow(unused_variables))]
+async fn start_ble_advertising(
+    app: tauri::AppHandle,
+    adv_state: tauri::State<'_, AdvState>,
+    name: Option<String>,
+    service_uuid: Option<String>,
+) -> Result<(), PastoError> {
+    // 未指定时使用设置中的设备名和剪贴板服务 UUID
+    let settings = ble_settings(&app)?;
+    let name = name.or(Some(settings.device_name));
+    let service_uuid = service_uuid.or_else(|| Some(ble::CLIPBOARD_SERVICE_UUID.to_string()));
+
+    debug!("Parameters - name: {:?}, service_uuid: {:?}", name, service_uuid);
+
+    #[cfg(target_os = "macos")]
+    {
+        debug!("Starting BLE advertising on macOS...");
+        
+        // 持有锁直到新进程保存完毕，避免同时启动多个广播进程
+        let mut guard = lock_adv_state(&adv_state)?;
+        
+        // 如果已在广播，先停止旧进程
+        if let Some(advertiser) = guard.take() {
+            debug!("Stopping existing advertising process...");
+            kill_advertiser(advertiser);
+        }
+
+        // 检查ble-adv二进制文件是否存在
+        let ble_adv_path = std::path::Path::new("src-tauri/ble-adv/ble-adv");
+        debug!("Checking ble-adv binary file:");
+        debug!("- Path: {:?}", ble_adv_path);
+        debug!("- Exists: {}", ble_adv_path.exists());
+        if ble_adv_path.exists() {
+            if let Ok(metadata) = std::fs::metadata(ble_adv_path) {
+                debug!("- File size: {} bytes", metadata.len());
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    let mode = metadata.permissions().mode();
+                    debug!("- Permissions: {:o}", mode);
+                    debug!("- Is executable: {}", mode & 0o111 != 0);
+                }
+            }
+        }
+
+        // 获取sidecar路径，这在开发和发布模式下都能正确工作
+        debug!("Getting sidecar command for 'ble-adv'...");
+        let mut cmd = app.shell().sidecar("ble-adv")
+            .map_err(|e| {
+                let error_msg = format!("Failed to get sidecar command: {}", e);
+                error!("{}", error_msg);
+                error_msg
+            })?;
+        debug!("Sidecar command created successfully");
+        
+        if let Some(n) = &name { 
+            cmd = cmd.arg("--name").arg(n); 
+            debug!("Added --name argument: {}", n);
+        }
+        if let Some(u) = &service_uuid { 
+            cmd = cmd.arg("--uuid").arg(u); 
+            debug!("Added --uuid argument: {}", u);
+        }
+        
+        debug!("Spawning ble-adv process...");
+        let (rx, child) = cmd
+            .spawn()
+            .map_err(|e| {
+                let error_msg = format!("failed to start advertiser: {e}");
+                error!("{}", error_msg);
+                error_msg
+            })?;
+        let pid = child.pid();
+        debug!("BLE advertising process {} spawned successfully", pid);
+
+        let (exited_tx, exited) = std::sync::mpsc::channel();
+        *guard = Some(Advertiser { child, exited });
+        drop(guard);
+        watch_advertiser(app.clone(), rx, pid, exited_tx);
+        advertising_changed(&app, true);
+        debug!("BLE advertising started successfully");
+        Ok(())
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        ble::start_ble_advertising_windows(name, service_uuid).await
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        Err("BLE advertising is currently supported only on macOS and Windows.".into())
+    }
+}
+
+// 停止 BLE 广播（支持 macOS 和 Windows）
+#[tauri::command]
+#[tracing::instrument(name = "advertise", skip_all)]
+// 广播子进程只在 macOS 上使用
+#[cfg_attr(not(target_os = "macos"), allow(unused_variables))]
+async fn stop_ble_advertising(
+    app: tauri::AppHandle,
+    adv_state: tauri::State<'_, AdvState>,
+) -> Result<(), PastoError> {
+    #[cfg(target_os = "macos")]
+    {
+        debug!("Stopping BLE advertising on macOS...");
+        let advertiser = lock_adv_state(&adv_state)?.take();
+        
+        if let Some(advertiser) = advertiser {
+            debug!("Found active advertising process, stopping...");
+            kill_advertiser(advertiser);
+            advertising_changed(&app, false);
+        } else {
+            debug!("No active advertising process found");
+        }
+        debug!("BLE advertising stop operation completed");
+        Ok(())
+    }
+    
+    #[cfg(target_os = "windows")]
+    {
+        // Windows平台的BLE广播停止功能
+        ble::stop_ble_advertising_windows().await
+    }
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        Err("BLE advertising is not supported on this platform".into())
+    }
+}
+
+// 广播进程是否在运行
+#[tauri::command]
+fn get_advertising_status(adv_state: tauri::State<AdvState>) -> Result<bool, PastoError> {
+    Ok(lock_adv_state(&adv_state)?.is_some())
+}
+
+fn advertising_active(app: &tauri::AppHandle) -> bool {
+    app.state::<AdvState>()
+        .lock()
+        .map(|guard| guard.is_some())
+        .unwrap_or(false)
+}
+
+fn lock_adv_state(adv_state: &AdvState) -> Result<MutexGuard<'_, Option<Advertiser>>, PastoError> {
+    adv_state.lock().map_err(|e| {
+        error!("Failed to lock adv_state: {}", e);
+        format!("Failed to lock adv_state: {}", e).into()
+    })
+}
+
+// 结束广播进程并等待其退出。sidecar 在 stdin 关闭时也会退出，但 CommandChild
+// 不能单独关闭 stdin，这里直接结束进程，系统会随之停止广播。
+// kill 只发出信号，进程真正退出后 watch_advertiser 才会收到 Terminated 并通知这里；
+// 退出应用时也要等到这一步，否则 Pasto 退出后广播进程可能仍在运行
+fn kill_advertiser(advertiser: Advertiser) {
+    let Advertiser { child, exited } = advertiser;
+    let pid = child.pid();
+    if let Err(e) = child.kill() {
+        warn!("Failed to stop advertising process {}: {}", pid, e);
+        return;
+    }
+    // 可能在异步命令中调用，等待期间让出工作线程，以便 watch_advertiser 继续运行
+    let result = tokio::task::block_in_place(|| exited.recv_timeout(ADVERTISER_EXIT_TIMEOUT));
+    match result {
+        // 发送端已释放说明 watch_advertiser 已经处理完进程退出
+        Ok(()) | Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+            info!("Stopped advertising process {}", pid)
+        }
+        Err(std::sync::mpsc::RecvTimeoutError::Timeout) => warn!(
+            "Advertising process {} did not exit within {:?}",
+            pid, ADVERTISER_EXIT_TIMEOUT
+        ),
+    }
+}
+
+// 把广播进程的输出写入日志；进程自行退出（如蓝牙被关闭）时清除状态
+#[cfg(target_os = "macos")]
+fn watch_advertiser(
+    app: tauri::AppHandle,
+    mut rx: tauri::async_runtime::Receiver<tauri_plugin_shell::process::CommandEvent>,
+    pid: u32,
+    exited: std::sync::mpsc::Sender<()>,
+) {
+    use tauri_plugin_shell::process::CommandEvent;
+    use tracing::Instrument;
+
+    tauri::async_runtime::spawn(
+        async move {
+            while let Some(event) = rx.recv().await {
+                match event {
+                    CommandEvent::Stdout(line) | CommandEvent::Stderr(line) => {
+                        debug!("ble-adv: {}", String::from_utf8_lossy(&line).trim_end());
+                    }
+                    CommandEvent::Error(e) => warn!("ble-adv: {}", e),
+                    CommandEvent::Terminated(payload) => {
+                        info!(
+                            "Advertising process {} exited (code {:?}, signal {:?})",
+                            pid, payload.code, payload.signal
+                        );
+                        // kill_advertiser 可能正在等待，已不再等待时发送失败可以忽略
+                        let _ = exited.send(());
+                        // 主动停止时状态已清除，这里只处理仍记录着该进程的情况
+                        let exited = match app.state::<AdvState>().lock() {
+                            Ok(mut guard) if guard.as_ref().is_some_and(|adv| adv.child.pid() == pid) => {
+                                *guard = None;
+                                true
+                            }
+                            _ => false,
+                        };
+                        if exited {
+                            advertising_changed(&app, false);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        .instrument(tracing::info_span!("advertise")),
+    );
+}
+
+#[cfg(target_os = "macos")]
+fn advertising_changed(app: &tauri::AppHandle, active: bool) {
+    if let Err(e) = app.emit("advertising-changed", active) {
+        error!("Failed to emit advertising-changed event: {}", e);
+    }
+    tray::refresh(app);
+}
+
+// 新增：连接到指定设备，并开始接收对方的剪贴板更新
+#[tauri::command]
+async fn connect_to_device(
+    app: tauri::AppHandle,
+    address: String,
+    name: Option<String>,
+) -> Result<(), PastoError> {
+    let settings = ble_settings(&app)?;
+    ble::connect_to_clipboard_device(
+        &address,
+        Duration::from_secs(settings.scan.connect_scan_secs),
+        settings.adapter.as_deref(),
+    )
+    .await?;
+
+    // 连接成功的设备加入托盘菜单的设备列表
+    let device = name.clone().unwrap_or_else(|| address.clone());
+    let new_device = !settings
+        .trusted_devices
+        .iter()
+        .any(|trusted| trusted.address == address);
+    if let Err(e) = change_settings(&app, |settings| settings.trust_device(&address, name)) {
+        error!("Failed to remember device {}: {}", address, e);
+    }
+    tray::refresh(&app);
+    notify::connection_changed(
+        &app,
+        notify::ConnectionEvent::Connected {
+            device: &device,
+            new_device,
+        },
+    );
+
+    tauri::async_runtime::spawn(async move {
+        let handle = app.clone();
+        let result = ble::listen_for_clipboard_updates(move |received| {
+            apply_received_clipboard(&handle, received);
+        })
+        .await;
+        if let Err(e) = result {
+            error!("Failed to listen for clipboard updates: {}", e);
+        }
+        // 监听失败时连接状态还在，清除后托盘才会显示未连接并允许重新连接
+        ble::disconnect(&address).await;
+        tray::refresh(&app);
+        notify::connection_changed(&app, notify::ConnectionEvent::Disconnected { device: &device });
+    });
+    Ok(())
+}
+
+fn sync_paused(app: &tauri::AppHandle) -> bool {
+    app.state::<SyncPausedState>().load(Ordering::SeqCst)
+}
+
+fn update_sync_paused(app: &tauri::AppHandle, paused: bool) {
+    app.state::<SyncPausedState>().store(paused, Ordering::SeqCst);
+    info!("Sync {}", if paused { "paused" } else { "resumed" });
+    if let Err(e) = app.emit("sync-paused-changed", paused) {
+        error!("Failed to emit sync-paused-changed event: {}", e);
+    }
+    tray::refresh(app);
+}
+
+#[tauri::command]
+fn get_sync_paused(app: tauri::AppHandle) -> bool {
+    sync_paused(&app)
+}
+
+#[tauri::command]
+fn set_sync_paused(app: tauri::AppHandle, paused: bool) {
+    update_sync_paused(&app, paused);
+}
+
+// 本机选区变化：剪贴板变化通知前端，然后按共用的同步规则记录历史并同步到已连接设备，
+// 需要确认的敏感内容交给前端询问
+fn handle_local_change(app: &tauri::AppHandle, selection: watcher::Selection, content: ClipboardContent) {
+    if selection == watcher::Selection::Clipboard {
+        if let Err(e) = app.emit("clipboard-changed", &content) {
+            error!("Failed to emit clipboard-changed event: {}", e);
+        }
+    }
+
+    let settings = current_settings(app);
+    let history = app.state::<Arc<HistoryStore>>();
+    match sync::local_change(&settings, &history, selection, content, sync_paused(app)) {
+        sync::Outgoing::Skip => {}
+        sync::Outgoing::Blocked(kind) => {
+            info!("Not syncing {:?}: looks like a {}", selection, kind.label());
+        }
+        sync::Outgoing::Confirm { text, kind } => {
+            let id = match app.state::<PendingSyncState>().lock() {
+                Ok(mut pending) => pending.submit(text),
+                Err(e) => {
+                    error!("Failed to lock pending confirmations: {}", e);
+                    return;
+                }
+            };
+            if let Err(e) = app.emit("sensitive-sync-confirmation", SensitiveSyncRequest { id, kind }) {
+                error!("Failed to emit sensitive-sync-confirmation event: {}", e);
+            }
+        }
+        sync::Outgoing::Send { text, options } => {
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = ble::send_clipboard_update(&text, options).await {
+                    error!("Failed to sync clipboard update: {}", e);
+                }
+            });
+        }
+    }
+}
+
+fn current_settings(app: &tauri::AppHandle) -> Settings {
+    app.state::<Arc<SettingsStore>>().get().unwrap_or_else(|e| {
+        warn!("Failed to read settings: {}", e.chain());
+        Settings::default()
+    })
+}
+
+// 按当前连接设备的配置对内容做变换
+fn apply_transforms(app: &tauri::AppHandle, direction: Direction, text: &str) -> String {
+    sync::apply_transforms(&current_settings(app), direction, text)
+}
+
+// 前端对敏感内容同步请求的答复
+#[tauri::command]
+async fn confirm_sensitive_sync(
+    app: tauri::AppHandle,
+    pending: tauri::State<'_, PendingSyncState>,
+    id: u64,
+    approve: bool,
+) -> Result<(), PastoError> {
+    let text = match pending.lock() {
+        Ok(mut pending) => pending.take(id),
+        Err(e) => return Err(format!("Failed to lock pending confirmations: {}", e).into()),
+    };
+    let Some(text) = text else {
+        return Err(format!("Sync request {} is no longer pending", id).into());
+    };
+
+    if approve {
+        let options = ble::SendOptions {
+            ttl_secs: current_settings(&app).rules.sensitive.ttl_secs,
+            kind: Some(content_kind::detect(&text)),
+            ..Default::default()
+        };
+        let text = apply_transforms(&app, Direction::Outgoing, &text);
+        ble::send_clipboard_update(&text, options).await
+    } else {
+        Ok(())
+    }
+}
+
+#[tauri::command]
+fn get_settings(settings: tauri::State<Arc<SettingsStore>>) -> Result<Settings, PastoError> {
+    settings.get()
+}
+
+// 整体替换设置，校验失败时返回所有错误且不做任何修改
+#[tauri::command]
+fn update_settings(app: tauri::AppHandle, new_settings: Settings) -> Result<Settings, PastoError> {
+    change_settings(&app, |settings| *settings = new_settings)
+}
+
+// 修改并保存设置，然后通知前端和正在运行的各部分
+fn change_settings(
+    app: &tauri::AppHandle,
+    change: impl FnOnce(&mut Settings),
+) -> Result<Settings, PastoError> {
+    let (previous, settings) = app.state::<Arc<SettingsStore>>().update(change)?;
+    if previous != settings {
+        on_settings_changed(app, &previous, &settings);
+    }
+    Ok(settings)
+}
+
+// 大部分设置在每次使用时读取，这里只处理需要主动生效的部分
+fn on_settings_changed(app: &tauri::AppHandle, previous: &Settings, settings: &Settings) {
+    if previous.logging != settings.logging {
+        logging::apply(&settings.logging);
+    }
+    if previous.history != settings.history {
+        let history = app.state::<Arc<HistoryStore>>();
+        if history.policy().ok().as_ref() != Some(&settings.history) {
+            if let Err(e) = history.set_policy(settings.history.clone()) {
+                error!("Failed to apply history retention: {}", e.chain());
+            }
+        }
+    }
+    if previous.sends() && !settings.sends() && ble::is_connected() {
+        info!("Sending disabled in settings, local changes will no longer sync");
+    }
+
+    if previous.hotkeys != settings.hotkeys {
+        hotkeys::apply(app, &settings.hotkeys);
+    }
+    if previous.trusted_devices != settings.trusted_devices {
+        tray::refresh(app);
+    }
+
+    if let Err(e) = app.emit("settings-changed", settings) {
+        error!("Failed to emit settings-changed event: {}", e);
+    }
+}
+
+#[tauri::command]
+fn get_sensitive_config(settings: tauri::State<Arc<SettingsStore>>) -> Result<SensitiveConfig, PastoError> {
+    Ok(settings.get()?.rules.sensitive)
+}
+
+#[tauri::command]
+fn set_sensitive_config(app: tauri::AppHandle, new_config: SensitiveConfig) -> Result<(), PastoError> {
+    change_settings(&app, |settings| settings.rules.sensitive = new_config).map(|_| ())
+}
+
+#[tauri::command]
+fn get_primary_sync_config(
+    settings: tauri::State<Arc<SettingsStore>>,
+) -> Result<PrimarySyncConfig, PastoError> {
+    Ok(settings.get()?.rules.primary)
+}
+
+// 设置某个设备的 PRIMARY 同步方式，Off 表示不同步
+#[tauri::command]
+fn set_primary_sync_mode(
+    app: tauri::AppHandle,
+    address: String,
+    mode: PrimarySyncMode,
+) -> Result<(), PastoError> {
+    change_settings(&app, |settings| settings.rules.primary.set_mode(address, mode)).map(|_| ())
+}
+
+#[tauri::command]
+fn get_transform_config(settings: tauri::State<Arc<SettingsStore>>) -> Result<TransformConfig, PastoError> {
+    Ok(settings.get()?.rules.transforms)
+}
+
+#[tauri::command]
+fn set_transform_config(app: tauri::AppHandle, new_config: TransformConfig) -> Result<(), PastoError> {
+    change_settings(&app, |settings| settings.rules.transforms = new_config).map(|_| ())
+}
+
+// 将收到的内容写入本机剪贴板并记录到历史，带有效期的内容到期后自动清除。
+// 发往 PRIMARY 的内容只写入选区，不记录历史
+fn apply_received_clipboard(app: &tauri::AppHandle, received: ble::ReceivedClipboard) {
+    if !current_settings(app).receives() || sync_paused(app) {
+        info!("Ignoring clipboard from {}: receiving disabled or paused", received.device);
+        return;
+    }
+    write_received_clipboard(app, received);
+}
+
+// 写入收到的内容，不检查同步方向和暂停状态（用户主动拉取时直接调用）
+fn write_received_clipboard(app: &tauri::AppHandle, received: ble::ReceivedClipboard) {
+    let settings = current_settings(app);
+    let clipboard_manager = app.state::<Arc<Mutex<ClipboardManager>>>();
+    let history = app.state::<Arc<HistoryStore>>();
+    let Some(applied) = sync::apply_received(&settings, &clipboard_manager, &history, received) else {
+        return;
+    };
+
+    // 通知会显示在系统通知中心，敏感内容不显示预览
+    notify::clipboard_received(
+        app,
+        &applied.device,
+        applied.class.as_ref(),
+        applied.sensitive,
+        &applied.content,
+    );
+    if let Some(entry) = &applied.entry {
+        if let Err(e) = app.emit("clipboard-received", entry) {
+            error!("Failed to emit clipboard-received event: {}", e);
+        }
+        tray::refresh(app);
+    }
+    if let Some(ttl_secs) = applied.undo_ttl_secs {
+        let clipboard_manager = clipboard_manager.inner().clone();
+        tauri::async_runtime::spawn(sync::expire_undo_after(clipboard_manager, ttl_secs));
+    }
+    if let Some(ttl_secs) = applied.ttl_secs {
+        let clipboard_manager = clipboard_manager.inner().clone();
+        tauri::async_runtime::spawn(sync::clear_after_ttl(clipboard_manager, applied.content, ttl_secs));
+    }
+}
+
+// 撤销最近一次远端内容对本机剪贴板的覆盖，返回是否有内容被恢复
+#[tauri::command]
+fn undo_remote_paste(app: tauri::AppHandle) -> Result<bool, PastoError> {
+    restore_before_remote_paste(&app)
+}
+
+// 导出诊断包到用户选择的位置
+#[tauri::command]
+async fn export_diagnostics(app: tauri::AppHandle, path: String) -> Result<(), PastoError> {
+    let settings = app.state::<Arc<SettingsStore>>().get()?;
+    let source = DiagnosticsSource {
+        app_version: app.package_info().version.to_string(),
+        settings,
+        log_dir: app.path().app_log_dir().ok(),
+    };
+    diagnostics::export(std::path::Path::new(&path), source)
+        .await
+        .inspect_err(|e| error!("Failed to export diagnostics: {}", e.chain()))?;
+    info!("Exported diagnostics to {}", path);
+    Ok(())
+}
+
+fn restore_before_remote_paste(app: &tauri::AppHandle) -> Result<bool, PastoError> {
+    let restored = match app.state::<Arc<Mutex<ClipboardManager>>>().lock() {
+        Ok(manager) => manager.undo_remote_text()?,
+        Err(e) => return Err(format!("Failed to lock clipboard manager: {}", e).into()),
+    };
+    if restored {
+        if let Err(e) = app.emit("remote-paste-undone", ()) {
+            error!("Failed to emit remote-paste-undone event: {}", e);
+        }
+    }
+    Ok(restored)
+}
+
+// 把本机当前剪贴板发送给已连接设备，敏感内容按设置拦截或附带有效期
+#[tauri::command]
+async fn push_clipboard(app: tauri::AppHandle) -> Result<(), PastoError> {
+    let text = match app.state::<Arc<Mutex<ClipboardManager>>>().lock() {
+        Ok(manager) => {
+            if manager.is_concealed().unwrap_or(false) {
+                return Err("Clipboard content is marked as concealed".into());
+            }
+            manager.get_text()?
+        }
+        Err(e) => return Err(format!("Failed to lock clipboard manager: {}", e).into()),
+    };
+
+    let (text, options) = sync::prepare_send(&current_settings(&app), &text)?;
+    ble::send_clipboard_update(&text, options).await
+}
+
+// 读取已连接设备的剪贴板并写入本机
+#[tauri::command]
+async fn pull_clipboard(app: tauri::AppHandle) -> Result<(), PastoError> {
+    let received = ble::read_peer_clipboard().await?;
+    write_received_clipboard(&app, received);
+    Ok(())
+}
+
+// 最近一次注册全局快捷键时无法注册的快捷键
+#[tauri::command]
+fn get_hotkey_conflicts(app: tauri::AppHandle) -> Vec<hotkeys::HotkeyConflict> {
+    hotkeys::conflicts(&app)
+}
+
+// 新增：发送剪贴板内容，ttl_secs 指定接收方自动清除的秒数
+#[tauri::command]
+async fn send_clipboard_content(
+    app: tauri::AppHandle,
+    content: String,
+    ttl_secs: Option<u64>,
+) -> Result<(), PastoError> {
+    let options = ble::SendOptions {
+        ttl_secs,
+        kind: Some(content_kind::detect(&content)),
+        ..Default::default()
+    };
+    let content = apply_transforms(&app, Direction::Outgoing, &content);
+    ble::send_clipboard_update(&content, options).await
+}
+
+// 分页获取剪贴板历史（按时间倒序）
+#[tauri::command]
+fn list_history(
+    history: tauri::State<Arc<HistoryStore>>,
+    offset: Option<u64>,
+    limit: Option<u64>,
+) -> Result<HistoryPage, PastoError> {
+    history.list(offset.unwrap_or(0), limit.unwrap_or(DEFAULT_HISTORY_PAGE_SIZE))
+}
+
+// 搜索剪贴板历史，fuzzy 为 true 时使用模糊匹配
+#[tauri::command]
+fn search_history(
+    history: tauri::State<Arc<HistoryStore>>,
+    query: String,
+    fuzzy: Option<bool>,
+    limit: Option<u64>,
+) -> Result<Vec<HistoryEntry>, PastoError> {
+    let limit = limit.unwrap_or(DEFAULT_HISTORY_PAGE_SIZE);
+    if fuzzy.unwrap_or(false) {
+        Ok(history.fuzzy_search(&query, limit)?)
+    } else {
+        Ok(history.search(&query, limit)?)
+    }
+}
+
+// 将历史记录重新复制到剪贴板
+#[tauri::command]
+fn recopy_history_item(
+    history: tauri::State<Arc<HistoryStore>>,
+    clipboard_manager: tauri::State<Arc<Mutex<ClipboardManager>>>,
+    id: i64,
+) -> Result<(), PastoError> {
+    let entry = history
+        .get(id)?
+        .ok_or_else(|| format!("History entry {} not found", id))?;
+    match clipboard_manager.lock() {
+        Ok(manager) => manager.set_text(&entry.content),
+        Err(e) => Err(format!("Failed to lock clipboard manager: {}", e).into()),
+    }
+}
+
+#[tauri::command]
+fn delete_history_item(history: tauri::State<Arc<HistoryStore>>, id: i64) -> Result<bool, PastoError> {
+    history.delete(id)
+}
+
+// 置顶的记录不会被保留策略清理
+#[tauri::command]
+fn pin_history_item(
+    history: tauri::State<Arc<HistoryStore>>,
+    id: i64,
+    pinned: bool,
+) -> Result<bool, PastoError> {
+    history.set_pinned(id, pinned)
+}
+
+#[tauri::command]
+fn get_history_retention(history: tauri::State<Arc<HistoryStore>>) -> Result<RetentionPolicy, PastoError> {
+    history.policy()
+}
+
+// 更新保留策略，返回按新策略清理掉的条数
+#[tauri::command]
+fn set_history_retention(
+    app: tauri::AppHandle,
+    history: tauri::State<Arc<HistoryStore>>,
+    policy: RetentionPolicy,
+) -> Result<u64, PastoError> {
+    // 先检查再清理，无效的策略不能删掉任何条目
+    let mut candidate = app.state::<Arc<SettingsStore>>().get()?;
+    candidate.history = policy.clone();
+    candidate.validate()?;
+    let removed = history.set_policy(policy.clone())?;
+    change_settings(&app, |settings| settings.history = policy)?;
+    Ok(removed)
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    // 新增广告状态
+    let adv_state: AdvState = Arc::new(Mutex::new(None));
+    let pending_sync: PendingSyncState = Arc::new(Mutex::new(PendingConfirmation::default()));
+    let sync_paused: SyncPausedState = Arc::new(AtomicBool::new(false));
+    let notifications = notify::NotificationState::default();
+
+    tauri::Builder::default()
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_dialog::init())
+        .manage(adv_state)
+        .manage(pending_sync)
+        .manage(sync_paused)
+        .manage(notifications)
+        .setup(move |app| {
+            // 日志写入应用日志目录，读取设置后再调整级别
+            let log_dir = app.path().app_log_dir().ok();
+            logging::init(LogLevel::default(), log_dir.as_deref());
+
+            // 没有可用的系统剪贴板时会退回内存剪贴板，BLE 同步和历史仍可使用
+            let clipboard_manager = Arc::new(Mutex::new(ClipboardManager::new()));
+            app.manage(clipboard_manager.clone());
+
+            // 设置保存在应用配置目录，文件损坏或版本过新时使用默认设置且不覆盖原文件
+            let settings = app
+                .path()
+                .app_config_dir()
+                .map_err(|e| PastoError::storage("Failed to resolve app config dir", e))
+                .and_then(|dir| SettingsStore::open(&dir.join(SETTINGS_FILE_NAME)))
+                .unwrap_or_else(|e| {
+                    warn!("Failed to load settings, using defaults: {}", e.chain());
+                    SettingsStore::in_memory()
+                });
+            let initial_settings = settings.get()?;
+            logging::apply(&initial_settings.logging);
+            hlc::set_node_id(&initial_settings.node_id);
+            app.manage(Arc::new(settings));
+
+            // 全局快捷键，无法注册的快捷键不影响启动
+            hotkeys::init(app.handle(), &initial_settings.hotkeys)?;
+
+            // 剪贴板历史保存在应用数据目录，无法访问时退回到内存
+            let history = app
+                .path()
+                .app_data_dir()
+                .map_err(|e| PastoError::storage("Failed to resolve app data dir", e))
+                .and_then(|dir| HistoryStore::open(&dir.join("history.db")))
+                .or_else(|e| {
+                    warn!("Failed to open clipboard history, using in-memory store: {}", e.chain());
+                    HistoryStore::open_in_memory()
+                })?;
+            history.set_policy(initial_settings.history)?;
+            let history = Arc::new(history);
+            app.manage(history.clone());
+            history::spawn_compaction(history, HISTORY_COMPACTION_INTERVAL);
+
+            // 托盘图标，关闭主窗口后仍可通过它控制同步
+            tray::create(app.handle())?;
+
+            // 剪贴板变化时通知前端、记录历史，并在已连接设备时自动同步
+            let handle = app.handle().clone();
+            watcher::spawn_clipboard_watcher(
+                clipboard_manager,
+                watcher::WatchOptions {
+                    watch_primary: cfg!(target_os = "linux"),
+                },
+                move |selection, content| handle_local_change(&handle, selection, content),
+            );
+            Ok(())
+        })
+        // 关闭主窗口时只隐藏，同步在后台继续，通过托盘菜单退出
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                if window.hide().is_ok() {
+                    api.prevent_close();
+                }
+            }
+        })
+        .invoke_handler(tauri::generate_handler![
+            greet,
+            get_clipboard_text,
+            scan_ble_devices,
+            start_ble_advertising,
+            stop_ble_advertising,
+            get_advertising_status,
+            connect_to_device,
+            send_clipboard_content,
+            list_history,
+            search_history,
+            recopy_history_item,
+            delete_history_item,
+            pin_history_item,
+            get_history_retention,
+            set_history_retention,
+            confirm_sensitive_sync,
+            get_sensitive_config,
+            set_sensitive_config,
+            get_primary_sync_config,
+            set_primary_sync_mode,
+            get_transform_config,
+            set_transform_config,
+            get_settings,
+            update_settings,
+            get_sync_paused,
+            set_sync_paused,
+            push_clipboard,
+            pull_clipboard,
+            get_hotkey_conflicts,
+            undo_remote_paste,
+            export_diagnostics
+        ])
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                // 退出前结束广播进程，避免 Pasto 退出后仍在广播
+                let advertiser = app.state::<AdvState>().lock().ok().and_then(|mut guard| guard.take());
+                if let Some(advertiser) = advertiser {
+                    kill_advertiser(advertiser);
+                }
+                // X11 下剪贴板内容属于 Pasto 进程，退出前交给桌面剪贴板管理器保管
+                if let Ok(manager) = app.state::<Arc<Mutex<ClipboardManager>>>().lock() {
+                    manager.shutdown();
+                }
+            }
+        });
+}
@@ -0,0 +1,153 @@
+use crate::settings::HotkeySettings;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+use tracing::{debug, error, warn};
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    Push,
+    Pull,
+    Pause,
+    Undo,
+}
+
+// 无法注册的快捷键：格式错误、与其他动作重复，或已被其他程序占用
+#[derive(Debug, Serialize, Clone)]
+pub struct HotkeyConflict {
+    pub action: HotkeyAction,
+    pub shortcut: String,
+    pub reason: String,
+}
+
+// 已注册的快捷键（按 Shortcut::id）和最近一次注册时的冲突
+#[derive(Default)]
+struct Registered {
+    actions: HashMap<u32, HotkeyAction>,
+    conflicts: Vec<HotkeyConflict>,
+}
+
+type HotkeyState = Mutex<Registered>;
+
+pub fn init(app: &AppHandle, settings: &HotkeySettings) -> tauri::Result<()> {
+    app.manage(HotkeyState::default());
+    app.plugin(
+        tauri_plugin_global_shortcut::Builder::new()
+            .with_handler(|app, shortcut, event| {
+                if event.state() != ShortcutState::Pressed {
+                    return;
+                }
+                if let Some(action) = action_for(app, shortcut) {
+                    trigger(app, action);
+                }
+            })
+            .build(),
+    )?;
+    apply(app, settings);
+    Ok(())
+}
+
+// 按设置重新注册全部快捷键，有冲突时通过 hotkey-conflicts 事件通知前端
+pub fn apply(app: &AppHandle, settings: &HotkeySettings) {
+    let global_shortcut = app.global_shortcut();
+    if let Err(e) = global_shortcut.unregister_all() {
+        error!("Failed to unregister global shortcuts: {}", e);
+    }
+
+    let mut registered = Registered::default();
+    for (action, text) in [
+        (HotkeyAction::Push, &settings.push),
+        (HotkeyAction::Pull, &settings.pull),
+        (HotkeyAction::Pause, &settings.pause),
+        (HotkeyAction::Undo, &settings.undo),
+    ] {
+        let Some(text) = text else {
+            continue;
+        };
+        let conflict = |reason: String| HotkeyConflict {
+            action,
+            shortcut: text.clone(),
+            reason,
+        };
+
+        let shortcut = match text.parse::<Shortcut>() {
+            Ok(shortcut) => shortcut,
+            Err(e) => {
+                registered.conflicts.push(conflict(format!("Invalid shortcut: {}", e)));
+                continue;
+            }
+        };
+        if let Some(other) = registered.actions.get(&shortcut.id()) {
+            registered
+                .conflicts
+                .push(conflict(format!("Already used for {:?}", other)));
+            continue;
+        }
+        match global_shortcut.register(shortcut) {
+            Ok(()) => {
+                registered.actions.insert(shortcut.id(), action);
+            }
+            Err(e) => registered.conflicts.push(conflict(e.to_string())),
+        }
+    }
+
+    for conflict in &registered.conflicts {
+        warn!(
+            "Failed to register {} for {:?}: {}",
+            conflict.shortcut, conflict.action, conflict.reason
+        );
+    }
+    if !registered.conflicts.is_empty() {
+        if let Err(e) = app.emit("hotkey-conflicts", &registered.conflicts) {
+            error!("Failed to emit hotkey-conflicts event: {}", e);
+        }
+    }
+
+    match app.state::<HotkeyState>().lock() {
+        Ok(mut state) => *state = registered,
+        Err(e) => error!("Failed to lock hotkey state: {}", e),
+    }
+}
+
+pub fn conflicts(app: &AppHandle) -> Vec<HotkeyConflict> {
+    app.try_state::<HotkeyState>()
+        .and_then(|state| state.lock().ok().map(|state| state.conflicts.clone()))
+        .unwrap_or_default()
+}
+
+fn action_for(app: &AppHandle, shortcut: &Shortcut) -> Option<HotkeyAction> {
+    let state = app.state::<HotkeyState>();
+    let registered = state.lock().ok()?;
+    registered.actions.get(&shortcut.id()).copied()
+}
+
+fn trigger(app: &AppHandle, action: HotkeyAction) {
+    debug!("{:?} triggered", action);
+    match action {
+        HotkeyAction::Push => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = super::push_clipboard(app).await {
+                    error!("Failed to push clipboard: {}", e);
+                }
+            });
+        }
+        HotkeyAction::Pull => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = super::pull_clipboard(app).await {
+                    error!("Failed to pull clipboard: {}", e);
+                }
+            });
+        }
+        HotkeyAction::Pause => super::update_sync_paused(app, !super::sync_paused(app)),
+        HotkeyAction::Undo => {
+            if let Err(e) = super::restore_before_remote_paste(app) {
+                error!("Failed to undo remote paste: {}", e);
+            }
+        }
+    }
+}
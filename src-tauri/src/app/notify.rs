@@ -0,0 +1,177 @@
+use crate::content_kind::{ContentClass, ContentKind};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+use tracing::{debug, error};
+
+// 每个时间窗口内最多显示的通知数，超出的合并到下一条通知里
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(10);
+const MAX_NOTIFICATIONS_PER_WINDOW: usize = 3;
+// 通知中内容预览的最大字符数
+const PREVIEW_CHARS: usize = 80;
+
+pub enum ConnectionEvent<'a> {
+    Connected { device: &'a str, new_device: bool },
+    Disconnected { device: &'a str },
+}
+
+// 滑动窗口限流，记录被省略的通知数
+#[derive(Default)]
+pub struct RateLimiter {
+    shown: VecDeque<Instant>,
+    suppressed: u32,
+}
+
+impl RateLimiter {
+    // 允许显示时返回此前被省略的条数
+    fn allow(&mut self, now: Instant) -> Option<u32> {
+        while self
+            .shown
+            .front()
+            .is_some_and(|shown| now.duration_since(*shown) >= RATE_LIMIT_WINDOW)
+        {
+            self.shown.pop_front();
+        }
+        if self.shown.len() >= MAX_NOTIFICATIONS_PER_WINDOW {
+            self.suppressed += 1;
+            return None;
+        }
+        self.shown.push_back(now);
+        Some(std::mem::take(&mut self.suppressed))
+    }
+}
+
+pub type NotificationState = Mutex<RateLimiter>;
+
+// sensitive 为 true 时不显示内容预览
+pub fn clipboard_received(
+    app: &AppHandle,
+    device: &str,
+    class: Option<&ContentClass>,
+    sensitive: bool,
+    text: &str,
+) {
+    let settings = super::current_settings(app).notifications;
+    if !settings.received {
+        return;
+    }
+
+    let kind = class.map(|class| kind_label(class.kind)).unwrap_or("文本");
+    let body = if sensitive {
+        "敏感内容，已隐藏预览".to_string()
+    } else if settings.show_preview {
+        preview(text)
+    } else {
+        format!("{}，{} 个字符", kind, text.chars().count())
+    };
+    show(app, &format!("收到来自 {} 的{}", device, kind), &body);
+}
+
+pub fn connection_changed(app: &AppHandle, event: ConnectionEvent) {
+    if !super::current_settings(app).notifications.connection {
+        return;
+    }
+    match event {
+        ConnectionEvent::Connected { device, new_device: true } => {
+            show(app, "已连接新设备", &format!("{} 已加入设备列表", device))
+        }
+        ConnectionEvent::Connected { device, new_device: false } => {
+            show(app, "已连接", &format!("已连接到 {}", device))
+        }
+        ConnectionEvent::Disconnected { device } => {
+            show(app, "连接已断开", &format!("与 {} 的连接已断开", device))
+        }
+    }
+}
+
+fn show(app: &AppHandle, title: &str, body: &str) {
+    let suppressed = match app.state::<NotificationState>().lock() {
+        Ok(mut limiter) => limiter.allow(Instant::now()),
+        Err(e) => {
+            error!("Failed to lock notification limiter: {}", e);
+            return;
+        }
+    };
+    let Some(suppressed) = suppressed else {
+        debug!("Rate limited: {}", title);
+        return;
+    };
+
+    let body = if suppressed > 0 {
+        format!("{}\n（另有 {} 条通知已省略）", body, suppressed)
+    } else {
+        body.to_string()
+    };
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        error!("Failed to show notification: {}", e);
+    }
+}
+
+fn kind_label(kind: ContentKind) -> &'static str {
+    match kind {
+        ContentKind::Url => "链接",
+        ContentKind::Email => "邮箱地址",
+        ContentKind::PhoneNumber => "电话号码",
+        ContentKind::FilePath => "文件路径",
+        ContentKind::Json => "JSON",
+        ContentKind::Code => "代码",
+        ContentKind::Color => "颜色",
+        ContentKind::OneTimeCode => "验证码",
+        ContentKind::Prose => "文本",
+    }
+}
+
+fn preview(text: &str) -> String {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut preview: String = line.chars().take(PREVIEW_CHARS).collect();
+    if line.chars().count() > PREVIEW_CHARS {
+        preview.push('…');
+    }
+    preview
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_up_to_the_limit_within_a_window() {
+        let mut limiter = RateLimiter::default();
+        let start = Instant::now();
+        for i in 0..MAX_NOTIFICATIONS_PER_WINDOW {
+            assert_eq!(limiter.allow(start + Duration::from_millis(i as u64)), Some(0));
+        }
+        assert_eq!(limiter.allow(start + Duration::from_secs(1)), None);
+        assert_eq!(limiter.allow(start + Duration::from_secs(2)), None);
+    }
+
+    #[test]
+    fn reports_suppressed_count_once_the_window_slides() {
+        let mut limiter = RateLimiter::default();
+        let start = Instant::now();
+        for _ in 0..MAX_NOTIFICATIONS_PER_WINDOW {
+            limiter.allow(start);
+        }
+        assert_eq!(limiter.allow(start + Duration::from_secs(1)), None);
+        assert_eq!(limiter.allow(start + Duration::from_secs(2)), None);
+
+        // 最早的通知移出窗口后放行，并带上被省略的条数
+        assert_eq!(limiter.allow(start + RATE_LIMIT_WINDOW), Some(2));
+        assert_eq!(limiter.suppressed, 0);
+    }
+
+    #[test]
+    fn window_is_sliding_not_fixed() {
+        let mut limiter = RateLimiter::default();
+        let start = Instant::now();
+        let step = RATE_LIMIT_WINDOW / 2;
+        assert_eq!(limiter.allow(start), Some(0));
+        assert_eq!(limiter.allow(start + step), Some(0));
+        assert_eq!(limiter.allow(start + step), Some(0));
+        // 第一条已移出窗口，另外两条仍在窗口内
+        assert_eq!(limiter.allow(start + RATE_LIMIT_WINDOW), Some(0));
+        assert_eq!(limiter.allow(start + RATE_LIMIT_WINDOW), None);
+    }
+}
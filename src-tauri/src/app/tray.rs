@@ -0,0 +1,227 @@
+use crate::ble;
+use crate::history::{HistoryEntry, HistoryStore};
+use crate::settings::{SettingsStore, TrustedDevice};
+use std::sync::Arc;
+use tauri::menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager, Wry};
+use tracing::error;
+
+const TRAY_ID: &str = "pasto";
+// 托盘菜单中列出的最近收到的条数
+const RECENT_RECEIVED_ITEMS: u64 = 5;
+// 菜单中内容预览的最大字符数
+const PREVIEW_CHARS: usize = 40;
+
+// 菜单项 ID，带参数的项以前缀加参数组成
+const MENU_SHOW: &str = "show";
+const MENU_PAUSE: &str = "pause";
+const MENU_START_ADVERTISING: &str = "start_advertising";
+const MENU_STOP_ADVERTISING: &str = "stop_advertising";
+const MENU_QUIT: &str = "quit";
+const MENU_CONNECT_PREFIX: &str = "connect:";
+const MENU_RECOPY_PREFIX: &str = "recopy:";
+
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    let menu = build_menu(app)?;
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip(tooltip())
+        .menu(&menu)
+        .show_menu_on_left_click(true)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id().as_ref()));
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    Ok(())
+}
+
+// 连接状态、暂停状态、设置或收到的内容变化后重建菜单
+pub fn refresh(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    match build_menu(app) {
+        Ok(menu) => {
+            if let Err(e) = tray.set_menu(Some(menu)) {
+                error!("Failed to update tray menu: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to build tray menu: {}", e),
+    }
+    if let Err(e) = tray.set_tooltip(Some(tooltip())) {
+        error!("Failed to update tray tooltip: {}", e);
+    }
+}
+
+fn tooltip() -> String {
+    match ble::connected_address() {
+        Some(address) => format!("Pasto - 已连接 {}", address),
+        None => "Pasto - 未连接".to_string(),
+    }
+}
+
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let status = MenuItem::with_id(app, "status", tooltip(), false, None::<&str>)?;
+    let show = MenuItem::with_id(app, MENU_SHOW, "显示主窗口", true, None::<&str>)?;
+    let pause = CheckMenuItem::with_id(
+        app,
+        MENU_PAUSE,
+        "暂停同步",
+        true,
+        super::sync_paused(app),
+        None::<&str>,
+    )?;
+    let advertising = super::advertising_active(app);
+    let start_advertising =
+        MenuItem::with_id(app, MENU_START_ADVERTISING, "开始广播", !advertising, None::<&str>)?;
+    let stop_advertising =
+        MenuItem::with_id(app, MENU_STOP_ADVERTISING, "停止广播", advertising, None::<&str>)?;
+    let devices = devices_submenu(app)?;
+    let recent = recent_submenu(app)?;
+    let quit = MenuItem::with_id(app, MENU_QUIT, "退出 Pasto", true, None::<&str>)?;
+
+    Menu::with_items(
+        app,
+        &[
+            &status,
+            &PredefinedMenuItem::separator(app)?,
+            &show,
+            &pause,
+            &start_advertising,
+            &stop_advertising,
+            &devices,
+            &recent,
+            &PredefinedMenuItem::separator(app)?,
+            &quit,
+        ],
+    )
+}
+
+fn devices_submenu(app: &AppHandle) -> tauri::Result<Submenu<Wry>> {
+    let trusted = app
+        .state::<Arc<SettingsStore>>()
+        .get()
+        .map(|settings| settings.trusted_devices)
+        .unwrap_or_default();
+    let connected = ble::connected_address();
+
+    let items = trusted
+        .iter()
+        .map(|device| {
+            let TrustedDevice { address, name } = device;
+            let label = match name {
+                Some(name) => format!("{} ({})", name, address),
+                None => address.clone(),
+            };
+            MenuItem::with_id(
+                app,
+                format!("{}{}", MENU_CONNECT_PREFIX, address),
+                label,
+                connected.as_deref() != Some(address.as_str()),
+                None::<&str>,
+            )
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+    submenu(app, "连接设备", "没有连接过的设备", items)
+}
+
+fn recent_submenu(app: &AppHandle) -> tauri::Result<Submenu<Wry>> {
+    let entries = app
+        .try_state::<Arc<HistoryStore>>()
+        .and_then(|history| history.recent_received(RECENT_RECEIVED_ITEMS).ok())
+        .unwrap_or_default();
+
+    let items = entries
+        .iter()
+        .map(|entry| {
+            MenuItem::with_id(
+                app,
+                format!("{}{}", MENU_RECOPY_PREFIX, entry.id),
+                preview(entry),
+                true,
+                None::<&str>,
+            )
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+    submenu(app, "最近收到", "还没有收到内容", items)
+}
+
+// 没有子项时放一个不可点击的提示
+fn submenu(
+    app: &AppHandle,
+    title: &str,
+    empty_label: &str,
+    items: Vec<MenuItem<Wry>>,
+) -> tauri::Result<Submenu<Wry>> {
+    let placeholder;
+    let items: Vec<&dyn IsMenuItem<Wry>> = if items.is_empty() {
+        placeholder = MenuItem::new(app, empty_label, false, None::<&str>)?;
+        vec![&placeholder]
+    } else {
+        items.iter().map(|item| item as &dyn IsMenuItem<Wry>).collect()
+    };
+    Submenu::with_items(app, title, true, &items)
+}
+
+fn preview(entry: &HistoryEntry) -> String {
+    let line = entry.content.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut preview: String = line.chars().take(PREVIEW_CHARS).collect();
+    if line.chars().count() > PREVIEW_CHARS {
+        preview.push('…');
+    }
+    match &entry.source_device {
+        Some(device) => format!("{} — {}", preview, device),
+        None => preview,
+    }
+}
+
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    match id {
+        MENU_SHOW => {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.show();
+                let _ = window.unminimize();
+                let _ = window.set_focus();
+            }
+        }
+        MENU_PAUSE => super::update_sync_paused(app, !super::sync_paused(app)),
+        MENU_START_ADVERTISING => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let adv_state = app.state::<super::AdvState>();
+                if let Err(e) = super::start_ble_advertising(app.clone(), adv_state, None, None).await {
+                    error!("Failed to start advertising from tray: {}", e);
+                }
+            });
+        }
+        MENU_STOP_ADVERTISING => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let adv_state = app.state::<super::AdvState>();
+                if let Err(e) = super::stop_ble_advertising(app.clone(), adv_state).await {
+                    error!("Failed to stop advertising from tray: {}", e);
+                }
+            });
+        }
+        MENU_QUIT => app.exit(0),
+        _ => {
+            if let Some(address) = id.strip_prefix(MENU_CONNECT_PREFIX) {
+                let app = app.clone();
+                let address = address.to_string();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = super::connect_to_device(app, address, None).await {
+                        error!("Failed to connect from tray: {}", e);
+                    }
+                });
+            } else if let Some(id) = id.strip_prefix(MENU_RECOPY_PREFIX) {
+                let Ok(id) = id.parse::<i64>() else {
+                    return;
+                };
+                if let Err(e) = super::recopy_history_item(app.state(), app.state(), id) {
+                    error!("Failed to re-copy history item from tray: {}", e);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,409 @@
+// Pasto 命令行工具，方便在脚本和编辑器中使用。
+// pastod 正在运行时 connect、send、recv --once、status 和修改已信任设备交给它处理，否则直接通过 BLE 连接设备
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+use std::io::{IsTerminal, Read};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri_app_lib::ble;
+use tauri_app_lib::daemon::{self, DaemonConfig, Received, Request, Response};
+use tauri_app_lib::history::HistoryStore;
+use tauri_app_lib::hlc;
+use tauri_app_lib::logging::{self, LogLevel};
+use tauri_app_lib::settings::{self, Settings, SettingsStore, TrustedDevice};
+use tauri_app_lib::sync;
+use tauri_app_lib::transform::Direction;
+
+#[derive(Parser)]
+#[command(name = "pasto", about = "Share the clipboard with nearby devices over BLE")]
+struct Cli {
+    // 与 pastod 相同的配置文件，用于找到设置、历史和控制 socket
+    /// Daemon config file (defaults to $XDG_CONFIG_HOME/pasto/pastod.json)
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    /// Print results as JSON
+    #[arg(long, global = true)]
+    json: bool,
+    /// Log to stderr (-v info, -vv debug, -vvv trace)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Scan for devices advertising the Pasto service
+    Scan {
+        /// Scan duration in seconds (defaults to the value in settings)
+        #[arg(long)]
+        secs: Option<u64>,
+    },
+    /// Connect to a device and remember it as trusted
+    Connect {
+        /// Device address, or the name of a trusted device
+        id: String,
+    },
+    /// Send text to the connected device
+    Send {
+        /// Text to send; reads stdin when omitted or "-"
+        text: Option<String>,
+        /// Device to send to; pastod switches to it when running
+        #[arg(long)]
+        device: Option<String>,
+    },
+    /// Print clipboard items received from a device
+    Recv {
+        /// Exit after the first item
+        #[arg(long)]
+        once: bool,
+        /// Device to receive from (defaults to the device pastod is connected to, or the last trusted device)
+        #[arg(long)]
+        device: Option<String>,
+    },
+    /// Browse the clipboard history
+    History {
+        #[command(subcommand)]
+        command: HistoryCommand,
+    },
+    /// Manage trusted devices
+    Devices {
+        #[command(subcommand)]
+        command: DevicesCommand,
+    },
+    /// Show whether pastod is running and connected
+    Status,
+}
+
+#[derive(Subcommand)]
+enum HistoryCommand {
+    /// List the most recent items
+    List {
+        #[arg(long, default_value_t = 20)]
+        limit: u64,
+        #[arg(long, default_value_t = 0)]
+        offset: u64,
+    },
+    /// Search items containing the query
+    Search {
+        query: String,
+        #[arg(long, default_value_t = 20)]
+        limit: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum DevicesCommand {
+    /// List trusted devices
+    List,
+    /// Trust a device
+    Trust {
+        address: String,
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Forget a trusted device
+    Forget { id: String },
+}
+
+// 没有 pastod 时 status 的输出
+#[derive(Serialize)]
+struct LocalStatus {
+    running: bool,
+    socket: PathBuf,
+    trusted_devices: usize,
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    // 日志默认关闭，避免与错误信息重复；只写 stderr，不影响 --json 的输出
+    let level = match cli.verbose {
+        0 => None,
+        1 => Some(LogLevel::Info),
+        2 => Some(LogLevel::Debug),
+        _ => Some(LogLevel::Trace),
+    };
+    if let Some(level) = level {
+        logging::init(level, None);
+    }
+    if let Err(e) = run(cli).await {
+        eprintln!("pasto: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), String> {
+    let config_path = cli.config.clone().unwrap_or_else(DaemonConfig::default_path);
+    let config = DaemonConfig::load(&config_path)?;
+    let output = Output { json: cli.json };
+
+    match cli.command {
+        Command::Scan { secs } => {
+            let settings = ble_settings(&config)?;
+            let secs = secs.unwrap_or(settings.scan.scan_secs);
+            let devices =
+                ble::scan_ble_devices_once(Duration::from_secs(secs), settings.adapter.as_deref()).await?;
+            output.list(&devices, |device| {
+                let rssi = device.rssi.map(|rssi| format!("{} dBm", rssi)).unwrap_or_default();
+                format!("{}\t{}\t{}", device.address, device.name.as_deref().unwrap_or("-"), rssi)
+            })
+        }
+        Command::Connect { id } => {
+            let address = resolve_device(&config, &id)?;
+            if let Some(response) = daemon::request(&config.socket, &Request::Connect { address: address.clone() }) {
+                daemon_result(response)?;
+            } else {
+                connect(&config, &address).await?;
+            }
+            output.done(&format!("Connected to {}", address))
+        }
+        Command::Send { text, device } => {
+            let text = read_text(text)?;
+            let address = match device {
+                Some(id) => Some(resolve_device(&config, &id)?),
+                None => None,
+            };
+            let request = Request::Send {
+                text: text.clone(),
+                address: address.clone(),
+            };
+            match daemon::request(&config.socket, &request) {
+                Some(response) => {
+                    daemon_result(response)?;
+                }
+                None => {
+                    let address = match address {
+                        Some(address) => address,
+                        None => default_device(&config, None)?,
+                    };
+                    connect(&config, &address).await?;
+                    let (text, options) = sync::prepare_send(&settings(&config)?, &text)?;
+                    ble::send_clipboard_update(&text, options).await?;
+                }
+            }
+            output.done("Sent")
+        }
+        // pastod 已占用 BLE 连接，只能通过它一次取一条
+        Command::Recv { once, device } => {
+            if daemon::request(&config.socket, &Request::Status).is_some() {
+                if !once {
+                    return Err(
+                        "pastod is running and holds the BLE connection; use `pasto recv --once` or stop pastod"
+                            .to_string(),
+                    );
+                }
+                let address = match device {
+                    Some(id) => Some(resolve_device(&config, &id)?),
+                    None => None,
+                };
+                let response = daemon::request(&config.socket, &Request::Receive { address })
+                    .ok_or("pastod stopped before receiving")?;
+                let received: Received = serde_json::from_value(daemon_result(response)?.unwrap_or_default())
+                    .map_err(|e| format!("Invalid response from pastod: {}", e))?;
+                return output.value(&received, || received.content.clone());
+            }
+            let address = default_device(&config, device)?;
+            connect(&config, &address).await?;
+            receive(&config, &output, once).await
+        }
+        Command::History { command } => {
+            let history = HistoryStore::open(&config.history)?;
+            let entries = match command {
+                HistoryCommand::List { limit, offset } => history.list(offset, limit)?.entries,
+                HistoryCommand::Search { query, limit } => history.search(&query, limit)?,
+            };
+            output.list(&entries, |entry| {
+                format!(
+                    "{}\t{}\t{}",
+                    entry.id,
+                    entry.source_device.as_deref().unwrap_or("local"),
+                    entry.content.replace('\n', "\\n")
+                )
+            })
+        }
+        // pastod 运行时由它修改已信任设备，否则直接写设置文件
+        Command::Devices { command } => match command {
+            DevicesCommand::List => output.list(&settings(&config)?.trusted_devices, |device| {
+                format!("{}\t{}", device.address, device.name.as_deref().unwrap_or("-"))
+            }),
+            DevicesCommand::Trust { address, name } => {
+                let request = Request::Trust {
+                    address: address.clone(),
+                    name: name.clone(),
+                };
+                match daemon::request(&config.socket, &request) {
+                    Some(response) => {
+                        daemon_result(response)?;
+                    }
+                    None => {
+                        SettingsStore::open(&config.settings)?
+                            .update(|settings| settings.trust_device(&address, name))?;
+                    }
+                }
+                output.done(&format!("Trusted {}", address))
+            }
+            DevicesCommand::Forget { id } => {
+                let address = match daemon::request(&config.socket, &Request::Forget { id: id.clone() }) {
+                    Some(response) => serde_json::from_value(daemon_result(response)?.unwrap_or_default())
+                        .map_err(|e| format!("Invalid response from pastod: {}", e))?,
+                    None => SettingsStore::open(&config.settings)?.forget_device(&id)?,
+                };
+                output.done(&format!("Forgot {}", address))
+            }
+        },
+        Command::Status => match daemon::request(&config.socket, &Request::Status) {
+            Some(response) => {
+                let data = daemon_result(response)?.unwrap_or_default();
+                let status: daemon::DaemonStatus = serde_json::from_value(data)
+                    .map_err(|e| format!("Invalid status from pastod: {}", e))?;
+                output.value(&status, || {
+                    format!(
+                        "pastod running ({} clipboard)\nconnected: {}\npaused: {}",
+                        status.backend,
+                        status.connected.as_deref().unwrap_or("no"),
+                        status.paused
+                    )
+                })
+            }
+            None => {
+                let status = LocalStatus {
+                    running: false,
+                    socket: config.socket.clone(),
+                    trusted_devices: settings(&config)?.trusted_devices.len(),
+                };
+                output.value(&status, || {
+                    format!("pastod is not running (socket {})", status.socket.display())
+                })
+            }
+        },
+    }
+}
+
+// 文本或 JSON 输出
+struct Output {
+    json: bool,
+}
+
+impl Output {
+    fn value<T: Serialize>(&self, value: &T, text: impl FnOnce() -> String) -> Result<(), String> {
+        if self.json {
+            let json = serde_json::to_string(value).map_err(|e| format!("Failed to serialize output: {}", e))?;
+            println!("{}", json);
+        } else {
+            println!("{}", text());
+        }
+        Ok(())
+    }
+
+    fn list<T: Serialize>(&self, items: &[T], line: impl Fn(&T) -> String) -> Result<(), String> {
+        self.value(&items, || items.iter().map(&line).collect::<Vec<_>>().join("\n"))
+    }
+
+    fn done(&self, message: &str) -> Result<(), String> {
+        self.value(&serde_json::json!({ "ok": true }), || message.to_string())
+    }
+}
+
+// 只读取不写回，避免与正在运行的 pastod 同时写入设置文件。
+// 同时设置本机的时钟设备标识，直接发送时与桌面版和 pastod 使用同一标识
+fn settings(config: &DaemonConfig) -> Result<Settings, String> {
+    let settings = settings::load(&config.settings)?;
+    hlc::set_node_id(&settings.node_id);
+    Ok(settings)
+}
+
+// 读取设置并确认 BLE 传输已启用
+fn ble_settings(config: &DaemonConfig) -> Result<Settings, String> {
+    let settings = settings(config)?;
+    if !settings.transports.ble {
+        return Err("BLE transport is disabled in settings".to_string());
+    }
+    Ok(settings)
+}
+
+fn daemon_result(response: Result<Response, String>) -> Result<Option<serde_json::Value>, String> {
+    let response = response?;
+    if response.ok {
+        Ok(response.data)
+    } else {
+        Err(response.error.unwrap_or_else(|| "pastod request failed".to_string()))
+    }
+}
+
+// 设备可以用地址或已信任设备的名称指定
+fn resolve_device(config: &DaemonConfig, id: &str) -> Result<String, String> {
+    Ok(match settings(config)?.trusted_device(id) {
+        Some(TrustedDevice { address, .. }) => address.clone(),
+        None => id.to_string(),
+    })
+}
+
+// 未指定设备时使用最近加入的已信任设备
+fn default_device(config: &DaemonConfig, device: Option<String>) -> Result<String, String> {
+    match device {
+        Some(id) => resolve_device(config, &id),
+        None => settings(config)?
+            .trusted_devices
+            .last()
+            .map(|device| device.address.clone())
+            .ok_or_else(|| "No trusted devices; pass --device or run `pasto connect <id>` first".to_string()),
+    }
+}
+
+async fn connect(config: &DaemonConfig, address: &str) -> Result<(), String> {
+    let settings = ble_settings(config)?;
+    ble::connect_to_clipboard_device(
+        address,
+        Duration::from_secs(settings.scan.connect_scan_secs),
+        settings.adapter.as_deref(),
+    )
+    .await?;
+    SettingsStore::open(&config.settings)?.update(|settings| settings.trust_device(address, None))?;
+    Ok(())
+}
+
+// 打印收到的内容（不写入本机剪贴板），直到连接断开；once 时收到第一条后退出
+async fn receive(config: &DaemonConfig, output: &Output, once: bool) -> Result<(), String> {
+    let settings = settings(config)?;
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let listener = tokio::spawn(ble::listen_for_clipboard_updates(move |received| {
+        let _ = tx.send(received);
+    }));
+
+    while let Some(received) = rx.recv().await {
+        let received = Received {
+            content: sync::apply_transforms(&settings, Direction::Incoming, &received.content),
+            device: received.device,
+            kind: received.kind,
+            ttl_secs: received.ttl_secs,
+        };
+        output.value(&received, || received.content.clone())?;
+        if once {
+            listener.abort();
+            return Ok(());
+        }
+    }
+    listener
+        .await
+        .map_err(|e| format!("Failed to listen for clipboard updates: {}", e))?
+        .map_err(String::from)
+}
+
+// 参数为空或 "-" 时从标准输入读取
+fn read_text(text: Option<String>) -> Result<String, String> {
+    match text {
+        Some(text) if text != "-" => Ok(text),
+        _ => {
+            let mut stdin = std::io::stdin();
+            if stdin.is_terminal() {
+                return Err("No text given; pass TEXT or pipe it to stdin".to_string());
+            }
+            let mut text = String::new();
+            stdin
+                .read_to_string(&mut text)
+                .map_err(|e| format!("Failed to read stdin: {}", e))?;
+            Ok(text)
+        }
+    }
+}
@@ -0,0 +1,34 @@
+// Pasto 无界面守护进程：监听剪贴板并通过 BLE 同步，通过本地 socket 控制
+use std::path::PathBuf;
+use tauri_app_lib::daemon::{self, DaemonConfig};
+
+const USAGE: &str = "Usage: pastod [--config <path>]";
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut config_path = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-c" | "--config" => match args.next() {
+                Some(path) => config_path = Some(PathBuf::from(path)),
+                None => exit_with_usage("--config requires a path"),
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            other => exit_with_usage(&format!("Unknown argument: {}", other)),
+        }
+    }
+
+    let config_path = config_path.unwrap_or_else(DaemonConfig::default_path);
+    if let Err(e) = daemon::run(&config_path) {
+        eprintln!("pastod: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn exit_with_usage(message: &str) -> ! {
+    eprintln!("pastod: {}\n{}", message, USAGE);
+    std::process::exit(2);
+}
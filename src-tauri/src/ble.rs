@@ -1,12 +1,20 @@
-use btleplug::api::{Central, Manager as _, ScanFilter, Peripheral as _, WriteType, CharPropFlags};
-use btleplug::platform::{Manager, Peripheral};
+use btleplug::api::{Central, CentralState, Manager as _, ScanFilter, Peripheral as _, WriteType, CharPropFlags};
+use btleplug::platform::{Adapter, Manager, Peripheral};
+use crate::content_kind::ContentClass;
+use crate::error::PastoError;
+use crate::history;
+use crate::hlc;
+use crate::logging;
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::sync::Mutex;
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 use tokio::time::{sleep, Duration};
 
 // 剪贴板服务和特征的UUID
-const CLIPBOARD_SERVICE_UUID: &str = "12345678-1234-1234-1234-1234567890AB";
+pub const CLIPBOARD_SERVICE_UUID: &str = "12345678-1234-1234-1234-1234567890AB";
 const CLIPBOARD_CHAR_UUID: &str = "87654321-4321-4321-4321-BA0987654321";
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -19,261 +27,437 @@ pub struct BleDevice {
 #[derive(Debug, Serialize, Deserialize)]
 struct ClipboardData {
     content: String,
-    timestamp: u64,
+    // 混合逻辑时钟时间戳，旧版本发送的是秒级时间戳
+    timestamp: hlc::Timestamp,
+    // 接收方在多少秒后清除这条内容（前提是剪贴板仍是这条内容）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ttl_secs: Option<u64>,
+    // 接收方写入剪贴板还是 PRIMARY 选区，旧版本不带此字段时视为剪贴板
+    #[serde(default)]
+    target: SyncTarget,
+    // 发送方检测到的内容类型
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kind: Option<ContentClass>,
+}
+
+// 随内容一起发送的附加信息
+#[derive(Debug, Clone, Default)]
+pub struct SendOptions {
+    // 内容写入本机剪贴板时的时间戳，未指定时使用当前时间
+    pub timestamp: Option<hlc::Timestamp>,
+    pub ttl_secs: Option<u64>,
+    pub target: SyncTarget,
+    pub kind: Option<ContentClass>,
+}
+
+// 同步内容在接收方写入的位置
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncTarget {
+    #[default]
+    Clipboard,
+    Primary,
+}
+
+// 从已连接设备收到的剪贴板内容
+#[derive(Debug, Clone)]
+pub struct ReceivedClipboard {
+    pub content: String,
+    pub timestamp: hlc::Timestamp,
+    pub ttl_secs: Option<u64>,
+    pub target: SyncTarget,
+    pub kind: Option<ContentClass>,
+    // 发送方设备名（未知时为地址）
+    pub device: String,
 }
 
 // 全局连接状态
 static CONNECTED_PERIPHERAL: Mutex<Option<Peripheral>> = Mutex::new(None);
 
+// 以下记录用于导出诊断信息
+// 连接历史最多保留的条数
+const MAX_CONNECTION_EVENTS: usize = 50;
+
+static LAST_SCAN: Mutex<Option<ScanReport>> = Mutex::new(None);
+static LAST_DISCOVERY: Mutex<Option<ServiceDiscovery>> = Mutex::new(None);
+static CONNECTION_HISTORY: Mutex<VecDeque<ConnectionEvent>> = Mutex::new(VecDeque::new());
+
+// 扫描时看到的一个设备，包括不提供剪贴板服务的设备
+#[derive(Debug, Serialize, Clone)]
+pub struct ScannedPeripheral {
+    pub address: String,
+    pub name: Option<String>,
+    pub rssi: Option<i16>,
+    // 广播包中的服务 UUID
+    pub services: Vec<String>,
+    pub has_clipboard_service: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ScanReport {
+    pub timestamp_ms: u64,
+    pub peripherals: Vec<ScannedPeripheral>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DiscoveredCharacteristic {
+    pub uuid: String,
+    pub properties: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DiscoveredService {
+    pub uuid: String,
+    pub characteristics: Vec<DiscoveredCharacteristic>,
+}
+
+// 最近一次连接时发现的 GATT 服务和特征
+#[derive(Debug, Serialize, Clone)]
+pub struct ServiceDiscovery {
+    pub timestamp_ms: u64,
+    pub address: String,
+    pub services: Vec<DiscoveredService>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ConnectionEventKind {
+    Connected,
+    Disconnected,
+    Failed { code: &'static str, error: String },
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ConnectionEvent {
+    pub timestamp_ms: u64,
+    pub address: String,
+    #[serde(flatten)]
+    pub kind: ConnectionEventKind,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct AdapterReport {
+    pub info: String,
+    pub state: String,
+}
+
 // Windows平台的BLE广播功能（使用Windows原生API的占位符实现）
 #[cfg(target_os = "windows")]
-pub async fn start_ble_advertising_windows(_name: Option<String>, _service_uuid: Option<String>) -> Result<(), String> {
+pub async fn start_ble_advertising_windows(_name: Option<String>, _service_uuid: Option<String>) -> Result<(), PastoError> {
     // 注意：btleplug不支持Windows上的BLE广播功能
     // 这里需要使用Windows原生的Bluetooth API或其他库
     // 目前返回错误提示用户此功能在Windows上暂不可用
-    Err("BLE advertising on Windows requires native Windows Bluetooth API implementation. This feature is not yet implemented.".to_string())
+    Err("BLE advertising on Windows requires native Windows Bluetooth API implementation. This feature is not yet implemented.".into())
 }
 
 // Windows平台停止BLE广播
 #[cfg(target_os = "windows")]
-pub async fn stop_ble_advertising_windows() -> Result<(), String> {
+pub async fn stop_ble_advertising_windows() -> Result<(), PastoError> {
     // 对应的停止实现
-    Err("BLE advertising on Windows requires native Windows Bluetooth API implementation. This feature is not yet implemented.".to_string())
+    Err("BLE advertising on Windows requires native Windows Bluetooth API implementation. This feature is not yet implemented.".into())
 }
 
-pub async fn scan_ble_devices_once() -> Result<Vec<BleDevice>, String> {
-    println!("[BLE_SCAN_DEBUG] Starting BLE device scan...");
+// 选择蓝牙适配器：指定了 preferred 时使用信息中包含它的适配器，否则使用第一个
+async fn select_adapter(manager: &Manager, preferred: Option<&str>) -> Result<Adapter, PastoError> {
+    let adapters = manager
+        .adapters()
+        .await
+        .map_err(|e| PastoError::bluetooth("Failed to get adapters", e))?;
+    debug!("Found {} BLE adapters", adapters.len());
+
+    let adapter = match preferred {
+        None => adapters.into_iter().next().ok_or(PastoError::AdapterMissing)?,
+        Some(preferred) => {
+            let mut found = None;
+            for adapter in adapters {
+                let info = adapter.adapter_info().await.unwrap_or_default();
+                if info.contains(preferred) {
+                    debug!("Using adapter {}", info);
+                    found = Some(adapter);
+                    break;
+                }
+            }
+            found.ok_or_else(|| PastoError::AdapterNotFound(preferred.to_string()))?
+        }
+    };
+
+    // 蓝牙关闭时扫描和连接的报错各平台不同，这里先统一检查
+    if let Ok(CentralState::PoweredOff) = adapter.adapter_state().await {
+        return Err(PastoError::BluetoothOff);
+    }
+    Ok(adapter)
+}
+
+#[tracing::instrument(name = "scan", skip_all, fields(adapter = ?adapter))]
+pub async fn scan_ble_devices_once(
+    scan_duration: Duration,
+    adapter: Option<&str>,
+) -> Result<Vec<BleDevice>, PastoError> {
+    debug!("Starting BLE device scan...");
     
     let manager = Manager::new()
         .await
         .map_err(|e| {
-            let error_msg = format!("Failed to initialize BLE manager: {}", e);
-            println!("[BLE_SCAN_ERROR] {}", error_msg);
-            error_msg
+            let error = PastoError::bluetooth("Failed to initialize BLE manager", e);
+            error!("{}", error.chain());
+            error
         })?;
-    println!("[BLE_SCAN_DEBUG] BLE manager initialized");
+    debug!("BLE manager initialized");
 
-    let adapters = manager
-        .adapters()
+    let adapter = select_adapter(&manager, adapter)
         .await
-        .map_err(|e| {
-            let error_msg = format!("Failed to get adapters: {}", e);
-            println!("[BLE_SCAN_ERROR] {}", error_msg);
-            error_msg
-        })?;
-    println!("[BLE_SCAN_DEBUG] Found {} BLE adapters", adapters.len());
-
-    let Some(adapter) = adapters.into_iter().next() else {
-        let error_msg = "No Bluetooth adapters found".to_string();
-        println!("[BLE_SCAN_ERROR] {}", error_msg);
-        return Err(error_msg);
-    };
-    println!("[BLE_SCAN_DEBUG] Using first available adapter");
+        .inspect_err(|error| error!("{}", error.chain()))?;
 
     // Stop any ongoing scan, then start scan
-    println!("[BLE_SCAN_DEBUG] Stopping any previous scan...");
+    debug!("Stopping any previous scan...");
     adapter
         .stop_scan()
         .await
         .map_err(|e| {
-            let error_msg = format!("Failed to stop previous scan: {}", e);
-            println!("[BLE_SCAN_ERROR] {}", error_msg);
-            error_msg
+            let error = PastoError::bluetooth("Failed to stop previous scan", e);
+            error!("{}", error.chain());
+            error
         })?;
 
-    println!("[BLE_SCAN_DEBUG] Starting new scan...");
+    debug!("Starting new scan...");
     adapter
         .start_scan(ScanFilter::default())
         .await
         .map_err(|e| {
-            let error_msg = format!("Failed to start scan: {}", e);
-            println!("[BLE_SCAN_ERROR] {}", error_msg);
-            error_msg
+            let error = PastoError::bluetooth("Failed to start scan", e);
+            error!("{}", error.chain());
+            error
         })?;
 
     // Wait to gather results
-    println!("[BLE_SCAN_DEBUG] Scanning for 3 seconds...");
-    sleep(Duration::from_secs(3)).await;
+    debug!("Scanning for {:?}...", scan_duration);
+    sleep(scan_duration).await;
 
     let peripherals = adapter
         .peripherals()
         .await
         .map_err(|e| {
-            let error_msg = format!("Failed to get peripherals: {}", e);
-            println!("[BLE_SCAN_ERROR] {}", error_msg);
-            error_msg
+            let error = PastoError::bluetooth("Failed to get peripherals", e);
+            error!("{}", error.chain());
+            error
         })?;
-    println!("[BLE_SCAN_DEBUG] Found {} total peripherals", peripherals.len());
+    debug!("Found {} total peripherals", peripherals.len());
 
     let mut devices = Vec::new();
+    let mut scanned = Vec::new();
     let service_uuid = Uuid::parse_str(CLIPBOARD_SERVICE_UUID).unwrap();
-    println!("[BLE_SCAN_DEBUG] Looking for devices with clipboard service UUID: {}", service_uuid);
+    debug!("Looking for devices with clipboard service UUID: {}", service_uuid);
     
     for (i, peripheral) in peripherals.iter().enumerate() {
         let address = peripheral.address().to_string();
-        println!("[BLE_SCAN_DEBUG] Checking peripheral {}: {}", i + 1, address);
+        debug!("Checking peripheral {}: {}", i + 1, address);
         
         let props_opt = peripheral
             .properties()
             .await
             .map_err(|e| {
-                let error_msg = format!("Failed to get peripheral properties for {}: {}", address, e);
-                println!("[BLE_SCAN_ERROR] {}", error_msg);
-                error_msg
+                let error = PastoError::bluetooth(format!("Failed to get peripheral properties for {}", address), e);
+                error!("{}", error.chain());
+                error
             })?;
             
         if let Some(props) = props_opt {
             let name = props.local_name.clone();
             let rssi = props.rssi;
             
-            println!("[BLE_SCAN_DEBUG] Device {}: name={:?}, rssi={:?}, services={:?}", 
+            debug!("Device {}: name={:?}, rssi={:?}, services={:?}", 
                 address, name, rssi, props.services);
+            let has_clipboard_service = props.services.contains(&service_uuid);
+            scanned.push(ScannedPeripheral {
+                address: address.clone(),
+                name: name.clone(),
+                rssi,
+                services: props.services.iter().map(|uuid| uuid.to_string()).collect(),
+                has_clipboard_service,
+            });
             
             // 只返回包含剪贴板服务的设备
-            if props.services.contains(&service_uuid) {
-                println!("[BLE_SCAN_DEBUG] ✓ Device {} has clipboard service, adding to results", address);
+            if has_clipboard_service {
+                debug!("✓ Device {} has clipboard service, adding to results", address);
                 devices.push(BleDevice { name, address, rssi });
             } else {
-                println!("[BLE_SCAN_DEBUG] ✗ Device {} does not have clipboard service", address);
+                debug!("✗ Device {} does not have clipboard service", address);
             }
         } else {
-            println!("[BLE_SCAN_DEBUG] Device {} has no properties available", address);
+            debug!("Device {} has no properties available", address);
         }
     }
     
-    println!("[BLE_SCAN_DEBUG] Scan completed. Found {} devices with clipboard service", devices.len());
+    info!("Scan completed. Found {} devices with clipboard service", devices.len());
+    if let Ok(mut last_scan) = LAST_SCAN.lock() {
+        *last_scan = Some(ScanReport {
+            timestamp_ms: history::now_millis(),
+            peripherals: scanned,
+        });
+    }
     Ok(devices)
 }
 
-pub async fn connect_to_clipboard_device(address: &str) -> Result<(), String> {
-    println!("[BLE_DEBUG] Starting connection to device: {}", address);
+#[tracing::instrument(name = "connect", skip_all, fields(address = %address))]
+pub async fn connect_to_clipboard_device(
+    address: &str,
+    scan_duration: Duration,
+    adapter: Option<&str>,
+) -> Result<(), PastoError> {
+    let result = connect_peripheral(address, scan_duration, adapter).await;
+    record_connection_event(
+        address,
+        match &result {
+            Ok(()) => ConnectionEventKind::Connected,
+            Err(e) => ConnectionEventKind::Failed {
+                code: e.code(),
+                error: e.chain(),
+            },
+        },
+    );
+    result
+}
+
+async fn connect_peripheral(
+    address: &str,
+    scan_duration: Duration,
+    adapter: Option<&str>,
+) -> Result<(), PastoError> {
+    debug!("Starting connection to device: {}", address);
     
     let manager = Manager::new()
         .await
         .map_err(|e| {
-            let error_msg = format!("Failed to initialize BLE manager: {}", e);
-            println!("[BLE_ERROR] {}", error_msg);
-            error_msg
+            let error = PastoError::bluetooth("Failed to initialize BLE manager", e);
+            error!("{}", error.chain());
+            error
         })?;
-    println!("[BLE_DEBUG] BLE manager initialized successfully");
+    debug!("BLE manager initialized successfully");
 
-    let adapters = manager
-        .adapters()
+    let adapter = select_adapter(&manager, adapter)
         .await
-        .map_err(|e| {
-            let error_msg = format!("Failed to get adapters: {}", e);
-            println!("[BLE_ERROR] {}", error_msg);
-            error_msg
-        })?;
-    println!("[BLE_DEBUG] Found {} BLE adapters", adapters.len());
-
-    let Some(adapter) = adapters.into_iter().next() else {
-        let error_msg = "No Bluetooth adapters found".to_string();
-        println!("[BLE_ERROR] {}", error_msg);
-        return Err(error_msg);
-    };
-    println!("[BLE_DEBUG] Using BLE adapter");
+        .inspect_err(|error| error!("{}", error.chain()))?;
+    debug!("Using BLE adapter");
 
     // 开始扫描寻找目标设备
-    println!("[BLE_DEBUG] Starting scan for target device...");
+    debug!("Starting scan for target device...");
     adapter
         .start_scan(ScanFilter::default())
         .await
         .map_err(|e| {
-            let error_msg = format!("Failed to start scan: {}", e);
-            println!("[BLE_ERROR] {}", error_msg);
-            error_msg
+            let error = PastoError::bluetooth("Failed to start scan", e);
+            error!("{}", error.chain());
+            error
         })?;
 
-    sleep(Duration::from_secs(2)).await;
-    println!("[BLE_DEBUG] Scan completed, retrieving peripherals...");
+    sleep(scan_duration).await;
+    debug!("Scan completed, retrieving peripherals...");
 
     let peripherals = adapter
         .peripherals()
         .await
         .map_err(|e| {
-            let error_msg = format!("Failed to get peripherals: {}", e);
-            println!("[BLE_ERROR] {}", error_msg);
-            error_msg
+            let error = PastoError::bluetooth("Failed to get peripherals", e);
+            error!("{}", error.chain());
+            error
         })?;
-    println!("[BLE_DEBUG] Found {} peripherals", peripherals.len());
+    debug!("Found {} peripherals", peripherals.len());
 
     // 查找目标设备
-    println!("[BLE_DEBUG] Searching for target device with address: {}", address);
+    debug!("Searching for target device with address: {}", address);
     let target_peripheral = peripherals
         .into_iter()
         .find(|p| {
             let device_address = p.address().to_string();
-            println!("[BLE_DEBUG] Checking device: {}", device_address);
+            debug!("Checking device: {}", device_address);
             device_address == address
         })
         .ok_or_else(|| {
-            let error_msg = format!("Device with address {} not found", address);
-            println!("[BLE_ERROR] {}", error_msg);
-            error_msg
+            let error = PastoError::DeviceNotFound(address.to_string());
+            error!("{}", error);
+            error
         })?;
-    println!("[BLE_DEBUG] Target device found, attempting connection...");
+    debug!("Target device found, attempting connection...");
 
     // 连接到设备
     target_peripheral
         .connect()
         .await
         .map_err(|e| {
-            let error_msg = format!("Failed to connect to device: {}", e);
-            println!("[BLE_ERROR] {}", error_msg);
-            error_msg
+            let error = PastoError::bluetooth("Failed to connect to device", e);
+            error!("{}", error.chain());
+            error
         })?;
-    println!("[BLE_DEBUG] Successfully connected to device");
+    debug!("Successfully connected to device");
 
     // 发现服务
-    println!("[BLE_DEBUG] Discovering services...");
+    debug!("Discovering services...");
     target_peripheral
         .discover_services()
         .await
         .map_err(|e| {
-            let error_msg = format!("Failed to discover services: {}", e);
-            println!("[BLE_ERROR] {}", error_msg);
-            error_msg
+            let error = PastoError::bluetooth("Failed to discover services", e);
+            error!("{}", error.chain());
+            error
         })?;
     
     let services = target_peripheral.services();
-    println!("[BLE_DEBUG] Discovered {} services", services.len());
+    debug!("Discovered {} services", services.len());
     
     // 打印所有发现的服务UUID用于调试
     for (i, service) in services.iter().enumerate() {
-        println!("[BLE_DEBUG] Service {}: UUID = {}", i + 1, service.uuid);
+        debug!("Service {}: UUID = {}", i + 1, service.uuid);
         for (j, characteristic) in service.characteristics.iter().enumerate() {
-            println!("[BLE_DEBUG]   Characteristic {}: UUID = {}, Properties = {:?}", 
+            debug!("  Characteristic {}: UUID = {}, Properties = {:?}", 
                 j + 1, characteristic.uuid, characteristic.properties);
         }
     }
+    if let Ok(mut last_discovery) = LAST_DISCOVERY.lock() {
+        *last_discovery = Some(ServiceDiscovery {
+            timestamp_ms: history::now_millis(),
+            address: address.to_string(),
+            services: services
+                .iter()
+                .map(|service| DiscoveredService {
+                    uuid: service.uuid.to_string(),
+                    characteristics: service
+                        .characteristics
+                        .iter()
+                        .map(|characteristic| DiscoveredCharacteristic {
+                            uuid: characteristic.uuid.to_string(),
+                            properties: format!("{:?}", characteristic.properties),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        });
+    }
 
     // 查找剪贴板服务
     let service_uuid = Uuid::parse_str(CLIPBOARD_SERVICE_UUID).unwrap();
-    println!("[BLE_DEBUG] Looking for clipboard service with UUID: {}", service_uuid);
+    debug!("Looking for clipboard service with UUID: {}", service_uuid);
     
     let clipboard_service = services
         .iter()
         .find(|s| s.uuid == service_uuid)
         .ok_or_else(|| {
-            let error_msg = format!(
-                "Clipboard service not found. Expected UUID: {}, Available services: [{}]", 
-                service_uuid,
-                services.iter().map(|s| s.uuid.to_string()).collect::<Vec<_>>().join(", ")
-            );
-            println!("[BLE_ERROR] {}", error_msg);
-            error_msg
+            let error = PastoError::ServiceNotFound {
+                expected: service_uuid.to_string(),
+                available: services.iter().map(|s| s.uuid.to_string()).collect(),
+            };
+            error!("{}", error.chain());
+            error
         })?;
-    println!("[BLE_DEBUG] Clipboard service found successfully");
+    debug!("Clipboard service found successfully");
 
     // 查找剪贴板特征
     let char_uuid = Uuid::parse_str(CLIPBOARD_CHAR_UUID).unwrap();
-    println!("[BLE_DEBUG] Looking for clipboard characteristic with UUID: {}", char_uuid);
-    println!("[BLE_DEBUG] Service has {} characteristics", clipboard_service.characteristics.len());
+    debug!("Looking for clipboard characteristic with UUID: {}", char_uuid);
+    debug!("Service has {} characteristics", clipboard_service.characteristics.len());
     
     // 打印服务中的所有特征用于调试
     for (i, characteristic) in clipboard_service.characteristics.iter().enumerate() {
-        println!("[BLE_DEBUG] Characteristic {}: UUID = {}, Properties = {:?}", 
+        debug!("Characteristic {}: UUID = {}, Properties = {:?}", 
             i + 1, characteristic.uuid, characteristic.properties);
     }
     
@@ -282,34 +466,30 @@ pub async fn connect_to_clipboard_device(address: &str) -> Result<(), String> {
         .iter()
         .find(|c| c.uuid == char_uuid)
         .ok_or_else(|| {
-            let error_msg = format!(
-                "Clipboard characteristic not found. Expected UUID: {}, Available characteristics: [{}]",
-                char_uuid,
-                clipboard_service.characteristics.iter()
-                    .map(|c| c.uuid.to_string())
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            );
-            println!("[BLE_ERROR] {}", error_msg);
-            error_msg
+            let error = PastoError::CharacteristicNotFound {
+                expected: char_uuid.to_string(),
+                available: clipboard_service.characteristics.iter().map(|c| c.uuid.to_string()).collect(),
+            };
+            error!("{}", error.chain());
+            error
         })?;
-    println!("[BLE_DEBUG] Clipboard characteristic found successfully");
-    println!("[BLE_DEBUG] Characteristic properties: {:?}", clipboard_char.properties);
+    debug!("Clipboard characteristic found successfully");
+    debug!("Characteristic properties: {:?}", clipboard_char.properties);
 
     // 订阅通知
     if clipboard_char.properties.contains(CharPropFlags::NOTIFY) {
-        println!("[BLE_DEBUG] Characteristic supports notifications, subscribing...");
+        debug!("Characteristic supports notifications, subscribing...");
         target_peripheral
             .subscribe(clipboard_char)
             .await
             .map_err(|e| {
-                let error_msg = format!("Failed to subscribe to notifications: {}", e);
-                println!("[BLE_ERROR] {}", error_msg);
-                error_msg
+                let error = PastoError::bluetooth("Failed to subscribe to notifications", e);
+                error!("{}", error.chain());
+                error
             })?;
-        println!("[BLE_DEBUG] Successfully subscribed to notifications");
+        debug!("Successfully subscribed to notifications");
     } else {
-        println!("[BLE_DEBUG] Characteristic does not support notifications");
+        debug!("Characteristic does not support notifications");
     }
 
     // 保存连接的设备
@@ -317,13 +497,54 @@ pub async fn connect_to_clipboard_device(address: &str) -> Result<(), String> {
         let mut connected = CONNECTED_PERIPHERAL.lock().unwrap();
         *connected = Some(target_peripheral);
     }
-    println!("[BLE_DEBUG] Device connection completed successfully");
+    info!("Device connection completed successfully");
 
     Ok(())
 }
 
-pub async fn send_clipboard_update(content: &str) -> Result<(), String> {
-    println!("[BLE_SEND_DEBUG] Starting clipboard update send, content length: {} chars", content.len());
+pub fn is_connected() -> bool {
+    CONNECTED_PERIPHERAL
+        .lock()
+        .map(|connected| connected.is_some())
+        .unwrap_or(false)
+}
+
+// 当前连接设备的地址
+pub fn connected_address() -> Option<String> {
+    CONNECTED_PERIPHERAL
+        .lock()
+        .ok()
+        .and_then(|connected| connected.as_ref().map(|p| p.address().to_string()))
+}
+
+// 连接已断开时清除连接状态。只清除仍是该地址的连接，期间已连上其他设备时保持不变
+fn clear_connected(address: &str) -> Option<Peripheral> {
+    let mut connected = CONNECTED_PERIPHERAL.lock().ok()?;
+    if connected.as_ref().is_some_and(|p| p.address().to_string() == address) {
+        connected.take()
+    } else {
+        None
+    }
+}
+
+// 断开与指定设备的连接并清除连接状态，未连接该设备时什么也不做
+pub async fn disconnect(address: &str) {
+    let Some(peripheral) = clear_connected(address) else {
+        return;
+    };
+    match peripheral.disconnect().await {
+        Ok(()) => info!("Disconnected from {}", address),
+        Err(e) => debug!("Failed to disconnect from {}: {}", address, e),
+    }
+}
+
+#[tracing::instrument(name = "send", skip_all)]
+pub async fn send_clipboard_update(content: &str, options: SendOptions) -> Result<(), PastoError> {
+    debug!(
+        "Starting clipboard update send, content length: {} chars, contents: {}",
+        content.len(),
+        logging::contents(content)
+    );
     
     let peripheral = {
         let connected = CONNECTED_PERIPHERAL.lock().unwrap();
@@ -331,83 +552,262 @@ pub async fn send_clipboard_update(content: &str) -> Result<(), String> {
     };
 
     let Some(peripheral) = peripheral else {
-        let error_msg = "No device connected".to_string();
-        println!("[BLE_SEND_ERROR] {}", error_msg);
-        return Err(error_msg);
+        error!("No device connected");
+        return Err(PastoError::NotConnected);
     };
-    println!("[BLE_SEND_DEBUG] Connected device found");
+    debug!("Connected device found");
 
     // 创建剪贴板数据
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-    
+    let timestamp = options.timestamp.unwrap_or_else(hlc::now);
+
     let clipboard_data = ClipboardData {
         content: content.to_string(),
-        timestamp,
+        timestamp: timestamp.clone(),
+        ttl_secs: options.ttl_secs,
+        target: options.target,
+        kind: options.kind,
     };
-    println!("[BLE_SEND_DEBUG] Created clipboard data with timestamp: {}", timestamp);
+    debug!("Created clipboard data with timestamp: {:?}", timestamp);
 
     let data = serde_json::to_string(&clipboard_data)
         .map_err(|e| {
-            let error_msg = format!("Failed to serialize clipboard data: {}", e);
-            println!("[BLE_SEND_ERROR] {}", error_msg);
-            error_msg
+            let error = PastoError::Other(format!("Failed to serialize clipboard data: {}", e));
+            error!("{}", error.chain());
+            error
         })?;
-    println!("[BLE_SEND_DEBUG] Serialized data length: {} bytes", data.len());
+    debug!("Serialized data length: {} bytes", data.len());
 
     // 查找剪贴板特征
     let service_uuid = Uuid::parse_str(CLIPBOARD_SERVICE_UUID).unwrap();
     let char_uuid = Uuid::parse_str(CLIPBOARD_CHAR_UUID).unwrap();
-    println!("[BLE_SEND_DEBUG] Looking for service UUID: {} and characteristic UUID: {}", service_uuid, char_uuid);
+    debug!("Looking for service UUID: {} and characteristic UUID: {}", service_uuid, char_uuid);
     
     let services = peripheral.services();
-    println!("[BLE_SEND_DEBUG] Device has {} services available", services.len());
+    debug!("Device has {} services available", services.len());
     
     let clipboard_service = services
         .iter()
         .find(|s| s.uuid == service_uuid)
         .ok_or_else(|| {
-            let error_msg = format!(
-                "Clipboard service not found during send. Expected: {}, Available: [{}]",
-                service_uuid,
-                services.iter().map(|s| s.uuid.to_string()).collect::<Vec<_>>().join(", ")
-            );
-            println!("[BLE_SEND_ERROR] {}", error_msg);
-            error_msg
+            let error = PastoError::ServiceNotFound {
+                expected: service_uuid.to_string(),
+                available: services.iter().map(|s| s.uuid.to_string()).collect(),
+            };
+            error!("{}", error.chain());
+            error
         })?;
-    println!("[BLE_SEND_DEBUG] Clipboard service found");
+    debug!("Clipboard service found");
 
     let clipboard_char = clipboard_service
         .characteristics
         .iter()
         .find(|c| c.uuid == char_uuid)
         .ok_or_else(|| {
-            let error_msg = format!(
-                "Clipboard characteristic not found during send. Expected: {}, Available: [{}]",
-                char_uuid,
-                clipboard_service.characteristics.iter()
-                    .map(|c| c.uuid.to_string())
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            );
-            println!("[BLE_SEND_ERROR] {}", error_msg);
-            error_msg
+            let error = PastoError::CharacteristicNotFound {
+                expected: char_uuid.to_string(),
+                available: clipboard_service.characteristics.iter().map(|c| c.uuid.to_string()).collect(),
+            };
+            error!("{}", error.chain());
+            error
         })?;
-    println!("[BLE_SEND_DEBUG] Clipboard characteristic found, properties: {:?}", clipboard_char.properties);
+    debug!("Clipboard characteristic found, properties: {:?}", clipboard_char.properties);
 
     // 写入数据
-    println!("[BLE_SEND_DEBUG] Writing {} bytes to characteristic...", data.as_bytes().len());
+    debug!("Writing {} bytes to characteristic...", data.len());
     peripheral
         .write(clipboard_char, data.as_bytes(), WriteType::WithoutResponse)
         .await
         .map_err(|e| {
-            let error_msg = format!("Failed to write clipboard data: {}", e);
-            println!("[BLE_SEND_ERROR] {}", error_msg);
-            error_msg
+            let error = PastoError::bluetooth("Failed to write clipboard data", e);
+            error!("{}", error.chain());
+            error
+        })?;
+    debug!("Clipboard data sent successfully");
+
+    Ok(())
+}
+
+// 主动读取已连接设备当前的剪贴板。对方返回的不是 JSON 时按纯文本处理。
+// 这是用户主动拉取，时间戳使用本机当前时间
+#[tracing::instrument(name = "receive", skip_all)]
+pub async fn read_peer_clipboard() -> Result<ReceivedClipboard, PastoError> {
+    let peripheral = {
+        let connected = CONNECTED_PERIPHERAL.lock().unwrap();
+        connected.clone()
+    };
+
+    let Some(peripheral) = peripheral else {
+        error!("No device connected");
+        return Err(PastoError::NotConnected);
+    };
+
+    let char_uuid = Uuid::parse_str(CLIPBOARD_CHAR_UUID).unwrap();
+    let clipboard_char = peripheral
+        .characteristics()
+        .into_iter()
+        .find(|c| c.uuid == char_uuid)
+        .ok_or_else(|| {
+            let error = PastoError::CharacteristicNotFound {
+                expected: char_uuid.to_string(),
+                available: peripheral.characteristics().iter().map(|c| c.uuid.to_string()).collect(),
+            };
+            error!("{}", error.chain());
+            error
+        })?;
+
+    let value = peripheral.read(&clipboard_char).await.map_err(|e| {
+        let error = PastoError::bluetooth("Failed to read clipboard characteristic", e);
+        error!("{}", error.chain());
+        error
+    })?;
+    debug!("Read {} bytes from {}", value.len(), peripheral.address());
+
+    let address = peripheral.address().to_string();
+    let device = peripheral
+        .properties()
+        .await
+        .ok()
+        .flatten()
+        .and_then(|props| props.local_name)
+        .unwrap_or(address);
+
+    let received = match serde_json::from_slice::<ClipboardData>(&value) {
+        Ok(data) => ReceivedClipboard {
+            content: data.content,
+            timestamp: hlc::now(),
+            ttl_secs: data.ttl_secs,
+            target: SyncTarget::Clipboard,
+            kind: data.kind,
+            device,
+        },
+        Err(_) => ReceivedClipboard {
+            content: String::from_utf8(value)
+                .map_err(|e| format!("Peer clipboard is not valid UTF-8: {}", e))?,
+            timestamp: hlc::now(),
+            ttl_secs: None,
+            target: SyncTarget::Clipboard,
+            kind: None,
+            device,
+        },
+    };
+    Ok(received)
+}
+
+// 监听已连接设备的剪贴板通知，直到连接断开
+#[tracing::instrument(name = "receive", skip_all)]
+pub async fn listen_for_clipboard_updates<F>(on_receive: F) -> Result<(), PastoError>
+where
+    F: Fn(ReceivedClipboard) + Send + 'static,
+{
+    let peripheral = {
+        let connected = CONNECTED_PERIPHERAL.lock().unwrap();
+        connected.clone()
+    };
+
+    let Some(peripheral) = peripheral else {
+        error!("No device connected");
+        return Err(PastoError::NotConnected);
+    };
+
+    let address = peripheral.address().to_string();
+    let device = peripheral
+        .properties()
+        .await
+        .ok()
+        .flatten()
+        .and_then(|props| props.local_name)
+        .unwrap_or_else(|| address.clone());
+
+    let char_uuid = Uuid::parse_str(CLIPBOARD_CHAR_UUID).unwrap();
+    let mut notifications = peripheral
+        .notifications()
+        .await
+        .map_err(|e| {
+            let error = PastoError::bluetooth("Failed to get notification stream", e);
+            error!("{}", error.chain());
+            error
         })?;
-    println!("[BLE_SEND_DEBUG] Clipboard data sent successfully");
+    info!("Listening for clipboard updates from {}", address);
+
+    while let Some(notification) = notifications.next().await {
+        if notification.uuid != char_uuid {
+            continue;
+        }
+        debug!("Received {} bytes from {}", notification.value.len(), address);
+
+        match serde_json::from_slice::<ClipboardData>(&notification.value) {
+            Ok(data) => {
+                debug!("Clipboard update from {}: {}", address, logging::contents(&data.content));
+                on_receive(ReceivedClipboard {
+                    content: data.content,
+                    timestamp: data.timestamp,
+                    ttl_secs: data.ttl_secs,
+                    target: data.target,
+                    kind: data.kind,
+                    device: device.clone(),
+                })
+            }
+            Err(e) => warn!("Failed to parse clipboard data: {}", e),
+        }
+    }
 
+    // 通知流结束说明连接已断开，清除连接状态以便重新连接
+    info!("Notification stream from {} ended", address);
+    disconnect(&address).await;
+    record_connection_event(&address, ConnectionEventKind::Disconnected);
     Ok(())
+}
+
+fn record_connection_event(address: &str, kind: ConnectionEventKind) {
+    if let Ok(mut events) = CONNECTION_HISTORY.lock() {
+        if events.len() == MAX_CONNECTION_EVENTS {
+            events.pop_front();
+        }
+        events.push_back(ConnectionEvent {
+            timestamp_ms: history::now_millis(),
+            address: address.to_string(),
+            kind,
+        });
+    }
+}
+
+// 本次运行中的连接记录，按时间从旧到新排列
+pub fn connection_history() -> Vec<ConnectionEvent> {
+    CONNECTION_HISTORY
+        .lock()
+        .map(|events| events.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+pub fn last_scan() -> Option<ScanReport> {
+    LAST_SCAN.lock().ok().and_then(|last_scan| last_scan.clone())
+}
+
+pub fn last_service_discovery() -> Option<ServiceDiscovery> {
+    LAST_DISCOVERY.lock().ok().and_then(|last_discovery| last_discovery.clone())
+}
+
+// 列出所有蓝牙适配器及其状态
+pub async fn list_adapters() -> Result<Vec<AdapterReport>, PastoError> {
+    let manager = Manager::new()
+        .await
+        .map_err(|e| PastoError::bluetooth("Failed to initialize BLE manager", e))?;
+    let adapters = manager
+        .adapters()
+        .await
+        .map_err(|e| PastoError::bluetooth("Failed to get adapters", e))?;
+
+    let mut reports = Vec::new();
+    for adapter in adapters {
+        let info = match adapter.adapter_info().await {
+            Ok(info) => info,
+            Err(e) => format!("unknown ({})", e),
+        };
+        let state = match adapter.adapter_state().await {
+            Ok(state) => format!("{:?}", state),
+            Err(e) => format!("unknown ({})", e),
+        };
+        reports.push(AdapterReport { info, state });
+    }
+    Ok(reports)
 }
\ No newline at end of file
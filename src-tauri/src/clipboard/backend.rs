@@ -0,0 +1,591 @@
+#[cfg(target_os = "linux")]
+use crate::watcher::Selection;
+use crate::error::PastoError;
+use arboard::Clipboard;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use tracing::{info_span, warn};
+
+// 剪贴板中可读写的内容类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContentType {
+    Text,
+    Html,
+}
+
+impl ContentType {
+    pub fn mime(&self) -> &'static str {
+        match self {
+            ContentType::Text => "text/plain;charset=utf-8",
+            ContentType::Html => "text/html",
+        }
+    }
+}
+
+// 剪贴板的底层实现，ClipboardManager 只通过这个接口访问剪贴板
+pub trait ClipboardBackend: Send {
+    fn name(&self) -> &'static str;
+
+    // 读取指定类型的内容，剪贴板中没有该类型时返回 None
+    fn get(&self, content_type: ContentType) -> Result<Option<Vec<u8>>, PastoError>;
+
+    fn set(&self, content_type: ContentType, data: &[u8]) -> Result<(), PastoError>;
+
+    fn clear(&self) -> Result<(), PastoError>;
+
+    // 剪贴板每次变化都会改变的标记，无法低成本获取时返回 None，由调用方比较内容
+    fn change_token(&self) -> Result<Option<u64>, PastoError> {
+        Ok(None)
+    }
+
+    // 剪贴板是否声明了指定格式（用于识别密码管理器的隐藏标记）
+    fn has_format(&self, _format: &str) -> Result<bool, PastoError> {
+        Ok(false)
+    }
+
+    // PRIMARY 选区（选中即复制、中键粘贴）的文本，不支持的后端始终返回 None
+    fn get_primary(&self) -> Result<Option<Vec<u8>>, PastoError> {
+        Ok(None)
+    }
+
+    fn set_primary(&self, _data: &[u8]) -> Result<(), PastoError> {
+        Err(format!("{} backend does not support the PRIMARY selection", self.name()).into())
+    }
+
+    // 应用退出前调用，需要时把剪贴板内容交给系统保管
+    fn shutdown(&self) {}
+}
+
+// 依次尝试 Wayland data-control、arboard、xclip/wl-copy 命令，都不可用时退回内存剪贴板，
+// 保证在无显示环境（CI、SSH）下也能启动
+pub fn detect() -> Box<dyn ClipboardBackend> {
+    let _span = info_span!("clipboard").entered();
+    if let Some(name) = std::env::var("PASTO_CLIPBOARD_BACKEND").ok().filter(|s| !s.is_empty()) {
+        match from_name(&name) {
+            Ok(backend) => return backend,
+            Err(e) => warn!("{}, detecting automatically", e),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            match WaylandBackend::new() {
+                Ok(backend) => return Box::new(backend),
+                Err(e) => warn!(
+                    "Wayland data-control unavailable, falling back to arboard: {}",
+                    e
+                ),
+            }
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        if std::env::var_os("DISPLAY").is_some() {
+            match X11Backend::new() {
+                Ok(backend) => return Box::new(backend),
+                Err(e) => warn!("X11 clipboard unavailable: {}", e),
+            }
+        }
+    }
+
+    match ArboardBackend::new() {
+        Ok(backend) => return Box::new(backend),
+        Err(e) => warn!("{}", e),
+    }
+
+    if let Some(backend) = CommandBackend::detect() {
+        return Box::new(backend);
+    }
+
+    warn!("No system clipboard available, using in-memory clipboard");
+    Box::new(MemoryBackend::new())
+}
+
+// 按名称创建后端：arboard、wayland、x11、command、memory
+pub fn from_name(name: &str) -> Result<Box<dyn ClipboardBackend>, PastoError> {
+    match name {
+        "arboard" => Ok(Box::new(ArboardBackend::new()?)),
+        #[cfg(target_os = "linux")]
+        "wayland" => Ok(Box::new(WaylandBackend::new()?)),
+        #[cfg(target_os = "linux")]
+        "x11" => Ok(Box::new(X11Backend::new()?)),
+        "command" => CommandBackend::detect()
+            .map(|backend| Box::new(backend) as Box<dyn ClipboardBackend>)
+            .ok_or_else(|| "Neither wl-copy nor xclip is available".into()),
+        "memory" => Ok(Box::new(MemoryBackend::new())),
+        _ => Err(format!("Unknown clipboard backend: {}", name).into()),
+    }
+}
+
+pub struct ArboardBackend {
+    clipboard: Mutex<Clipboard>,
+}
+
+impl ArboardBackend {
+    pub fn new() -> Result<Self, PastoError> {
+        Clipboard::new()
+            .map(|clipboard| Self {
+                clipboard: Mutex::new(clipboard),
+            })
+            .map_err(|e| PastoError::clipboard("Failed to initialize clipboard", e))
+    }
+}
+
+impl ClipboardBackend for ArboardBackend {
+    fn name(&self) -> &'static str {
+        "arboard"
+    }
+
+    fn get(&self, content_type: ContentType) -> Result<Option<Vec<u8>>, PastoError> {
+        let mut clipboard = self
+            .clipboard
+            .lock()
+            .map_err(|e| format!("Failed to lock clipboard: {}", e))?;
+        let result = match content_type {
+            ContentType::Text => clipboard.get_text(),
+            ContentType::Html => clipboard.get().html(),
+        };
+        match result {
+            Ok(text) => Ok(Some(text.into_bytes())),
+            Err(arboard::Error::ContentNotAvailable) => Ok(None),
+            Err(e) => Err(PastoError::clipboard(format!("Failed to get {} from clipboard", content_type.mime()), e)),
+        }
+    }
+
+    fn set(&self, content_type: ContentType, data: &[u8]) -> Result<(), PastoError> {
+        let text = String::from_utf8_lossy(data);
+        let mut clipboard = self
+            .clipboard
+            .lock()
+            .map_err(|e| format!("Failed to lock clipboard: {}", e))?;
+        let result = match content_type {
+            ContentType::Text => clipboard.set_text(text),
+            ContentType::Html => clipboard.set_html(text, None),
+        };
+        result.map_err(|e| PastoError::clipboard(format!("Failed to set {} to clipboard", content_type.mime()), e))
+    }
+
+    fn clear(&self) -> Result<(), PastoError> {
+        match self.clipboard.lock() {
+            Ok(mut clipboard) => clipboard
+                .clear()
+                .map_err(|e| PastoError::clipboard("Failed to clear clipboard", e)),
+            Err(e) => Err(format!("Failed to lock clipboard: {}", e).into()),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn get_primary(&self) -> Result<Option<Vec<u8>>, PastoError> {
+        use arboard::{GetExtLinux, LinuxClipboardKind};
+
+        let mut clipboard = self
+            .clipboard
+            .lock()
+            .map_err(|e| format!("Failed to lock clipboard: {}", e))?;
+        match clipboard.get().clipboard(LinuxClipboardKind::Primary).text() {
+            Ok(text) => Ok(Some(text.into_bytes())),
+            Err(arboard::Error::ContentNotAvailable) => Ok(None),
+            Err(e) => Err(PastoError::clipboard("Failed to get PRIMARY selection", e)),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn set_primary(&self, data: &[u8]) -> Result<(), PastoError> {
+        use arboard::{LinuxClipboardKind, SetExtLinux};
+
+        let mut clipboard = self
+            .clipboard
+            .lock()
+            .map_err(|e| format!("Failed to lock clipboard: {}", e))?;
+        clipboard
+            .set()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text(String::from_utf8_lossy(data))
+            .map_err(|e| PastoError::clipboard("Failed to set PRIMARY selection", e))
+    }
+
+    fn has_format(&self, format: &str) -> Result<bool, PastoError> {
+        #[cfg(target_os = "linux")]
+        {
+            if std::env::var_os("DISPLAY").is_some() {
+                return Ok(super::x11_hints::has_target(format)?);
+            }
+            Ok(false)
+        }
+        #[cfg(target_os = "macos")]
+        {
+            Ok(super::macos_hints::has_type(format))
+        }
+        #[cfg(target_os = "windows")]
+        {
+            Ok(super::windows_hints::has_format(format))
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+        {
+            let _ = format;
+            Ok(false)
+        }
+    }
+}
+
+// Wayland 下通过 data-control 协议访问，窗口未聚焦时也能读取
+#[cfg(target_os = "linux")]
+pub struct WaylandBackend;
+
+#[cfg(target_os = "linux")]
+impl WaylandBackend {
+    pub fn new() -> Result<Self, PastoError> {
+        super::wayland::probe()?;
+        Ok(Self)
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl ClipboardBackend for WaylandBackend {
+    fn name(&self) -> &'static str {
+        "wayland"
+    }
+
+    fn get(&self, content_type: ContentType) -> Result<Option<Vec<u8>>, PastoError> {
+        Ok(super::wayland::get(content_type)?)
+    }
+
+    fn set(&self, content_type: ContentType, data: &[u8]) -> Result<(), PastoError> {
+        Ok(super::wayland::set(content_type, data)?)
+    }
+
+    fn clear(&self) -> Result<(), PastoError> {
+        Ok(super::wayland::clear()?)
+    }
+
+    fn get_primary(&self) -> Result<Option<Vec<u8>>, PastoError> {
+        Ok(super::wayland::get_primary()?)
+    }
+
+    fn set_primary(&self, data: &[u8]) -> Result<(), PastoError> {
+        Ok(super::wayland::set_primary(data)?)
+    }
+
+    fn has_format(&self, format: &str) -> Result<bool, PastoError> {
+        Ok(super::wayland::has_mime_type(format)?)
+    }
+}
+
+// X11 下写入由专门的线程持有所有权，Pasto 写入的内容不会随 arboard 的 Clipboard 释放而消失；
+// 读取仍交给 arboard
+#[cfg(target_os = "linux")]
+pub struct X11Backend {
+    reader: ArboardBackend,
+    owner: super::x11_owner::X11Owner,
+}
+
+#[cfg(target_os = "linux")]
+impl X11Backend {
+    pub fn new() -> Result<Self, PastoError> {
+        Ok(Self {
+            reader: ArboardBackend::new()?,
+            owner: super::x11_owner::X11Owner::new()?,
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl ClipboardBackend for X11Backend {
+    fn name(&self) -> &'static str {
+        "x11"
+    }
+
+    fn get(&self, content_type: ContentType) -> Result<Option<Vec<u8>>, PastoError> {
+        self.reader.get(content_type)
+    }
+
+    fn set(&self, content_type: ContentType, data: &[u8]) -> Result<(), PastoError> {
+        Ok(self.owner.set(Selection::Clipboard, content_type, data.to_vec())?)
+    }
+
+    fn clear(&self) -> Result<(), PastoError> {
+        Ok(self.owner.clear()?)
+    }
+
+    fn get_primary(&self) -> Result<Option<Vec<u8>>, PastoError> {
+        self.reader.get_primary()
+    }
+
+    fn set_primary(&self, data: &[u8]) -> Result<(), PastoError> {
+        Ok(self.owner.set(Selection::Primary, ContentType::Text, data.to_vec())?)
+    }
+
+    fn has_format(&self, format: &str) -> Result<bool, PastoError> {
+        Ok(super::x11_hints::has_target(format)?)
+    }
+
+    fn shutdown(&self) {
+        self.owner.shutdown();
+    }
+}
+
+// 调用外部命令读写剪贴板
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipboardCommand {
+    // wl-copy / wl-paste
+    WlClipboard,
+    Xclip,
+}
+
+pub struct CommandBackend {
+    command: ClipboardCommand,
+}
+
+impl CommandBackend {
+    pub fn new(command: ClipboardCommand) -> Self {
+        Self { command }
+    }
+
+    // 根据当前会话和 PATH 中可用的命令选择 wl-clipboard 或 xclip
+    pub fn detect() -> Option<Self> {
+        let command = if std::env::var_os("WAYLAND_DISPLAY").is_some()
+            && command_exists("wl-copy")
+            && command_exists("wl-paste")
+        {
+            ClipboardCommand::WlClipboard
+        } else if std::env::var_os("DISPLAY").is_some() && command_exists("xclip") {
+            ClipboardCommand::Xclip
+        } else {
+            return None;
+        };
+        Some(Self::new(command))
+    }
+
+    fn paste_command(&self, target: &str, primary: bool) -> Command {
+        match self.command {
+            ClipboardCommand::WlClipboard => {
+                let mut cmd = Command::new("wl-paste");
+                cmd.args(["--no-newline", "--type", target]);
+                if primary {
+                    cmd.arg("--primary");
+                }
+                cmd
+            }
+            ClipboardCommand::Xclip => {
+                let mut cmd = Command::new("xclip");
+                let selection = if primary { "primary" } else { "clipboard" };
+                cmd.args(["-selection", selection, "-o", "-t", target]);
+                cmd
+            }
+        }
+    }
+
+    fn copy_command(&self, mime: &str, primary: bool) -> Command {
+        match self.command {
+            ClipboardCommand::WlClipboard => {
+                let mut cmd = Command::new("wl-copy");
+                cmd.args(["--type", mime]);
+                if primary {
+                    cmd.arg("--primary");
+                }
+                cmd
+            }
+            ClipboardCommand::Xclip => {
+                let mut cmd = Command::new("xclip");
+                let selection = if primary { "primary" } else { "clipboard" };
+                cmd.args(["-selection", selection, "-i", "-t", mime]);
+                cmd
+            }
+        }
+    }
+
+    // xclip 只认 UTF8_STRING 这类目标名，不认带 charset 的 MIME
+    fn target_for(&self, content_type: ContentType) -> &'static str {
+        match (self.command, content_type) {
+            (ClipboardCommand::Xclip, ContentType::Text) => "UTF8_STRING",
+            _ => content_type.mime(),
+        }
+    }
+
+    // 剪贴板为空或没有请求的类型时命令以非零状态退出，视为 None
+    fn read(&self, mut cmd: Command) -> Result<Option<Vec<u8>>, PastoError> {
+        let output = cmd
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .map_err(|e| format!("Failed to run clipboard command: {}", e))?;
+        if output.status.success() {
+            Ok(Some(output.stdout))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // wl-copy 和 xclip 读完标准输入后会在后台继续持有剪贴板，这里只等前台进程退出
+    fn write(&self, mut cmd: Command, data: &[u8]) -> Result<(), PastoError> {
+        let mut child = cmd
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to run clipboard command: {}", e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(data)
+                .map_err(|e| format!("Failed to write to clipboard command: {}", e))?;
+        }
+        let status = child
+            .wait()
+            .map_err(|e| format!("Failed to wait for clipboard command: {}", e))?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(format!("Clipboard command exited with {}", status).into())
+        }
+    }
+}
+
+impl ClipboardBackend for CommandBackend {
+    fn name(&self) -> &'static str {
+        match self.command {
+            ClipboardCommand::WlClipboard => "wl-clipboard",
+            ClipboardCommand::Xclip => "xclip",
+        }
+    }
+
+    fn get(&self, content_type: ContentType) -> Result<Option<Vec<u8>>, PastoError> {
+        self.read(self.paste_command(self.target_for(content_type), false))
+    }
+
+    fn set(&self, content_type: ContentType, data: &[u8]) -> Result<(), PastoError> {
+        self.write(self.copy_command(self.target_for(content_type), false), data)
+    }
+
+    fn clear(&self) -> Result<(), PastoError> {
+        match self.command {
+            ClipboardCommand::WlClipboard => {
+                let status = Command::new("wl-copy")
+                    .arg("--clear")
+                    .status()
+                    .map_err(|e| format!("Failed to run clipboard command: {}", e))?;
+                if status.success() {
+                    Ok(())
+                } else {
+                    Err(format!("Clipboard command exited with {}", status).into())
+                }
+            }
+            // xclip 没有清空选项，写入空文本代替
+            ClipboardCommand::Xclip => self.set(ContentType::Text, &[]),
+        }
+    }
+
+    fn has_format(&self, format: &str) -> Result<bool, PastoError> {
+        let listing = match self.command {
+            ClipboardCommand::WlClipboard => {
+                let mut cmd = Command::new("wl-paste");
+                cmd.arg("--list-types");
+                self.read(cmd)?
+            }
+            ClipboardCommand::Xclip => self.read(self.paste_command("TARGETS", false))?,
+        };
+        Ok(listing.is_some_and(|listing| {
+            String::from_utf8_lossy(&listing)
+                .lines()
+                .any(|line| line.trim() == format)
+        }))
+    }
+
+    fn get_primary(&self) -> Result<Option<Vec<u8>>, PastoError> {
+        self.read(self.paste_command(self.target_for(ContentType::Text), true))
+    }
+
+    fn set_primary(&self, data: &[u8]) -> Result<(), PastoError> {
+        self.write(self.copy_command(self.target_for(ContentType::Text), true), data)
+    }
+}
+
+fn command_exists(name: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|paths| {
+        std::env::split_paths(&paths).any(|dir| Path::new(&dir).join(name).is_file())
+    })
+}
+
+#[derive(Default)]
+struct MemoryClipboard {
+    contents: HashMap<ContentType, Vec<u8>>,
+    primary: Option<Vec<u8>>,
+    formats: HashSet<String>,
+    generation: u64,
+}
+
+// 进程内剪贴板，用于无显示环境和测试；克隆出的句柄共享同一份内容，
+// 测试可以持有一个句柄直接改写内容来模拟外部程序的复制操作
+#[derive(Clone, Default)]
+pub struct MemoryBackend {
+    inner: Arc<Mutex<MemoryClipboard>>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 为当前内容附加一个格式标记，例如密码管理器的隐藏标记
+    #[cfg(test)]
+    pub fn add_format(&self, format: &str) -> Result<(), PastoError> {
+        let mut inner = self.lock()?;
+        inner.formats.insert(format.to_string());
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, MemoryClipboard>, PastoError> {
+        self.inner
+            .lock()
+            .map_err(|e| format!("Failed to lock in-memory clipboard: {}", e).into())
+    }
+}
+
+impl ClipboardBackend for MemoryBackend {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn get(&self, content_type: ContentType) -> Result<Option<Vec<u8>>, PastoError> {
+        Ok(self.lock()?.contents.get(&content_type).cloned())
+    }
+
+    // 和系统剪贴板一样，每次写入都会替换掉之前的全部内容
+    fn set(&self, content_type: ContentType, data: &[u8]) -> Result<(), PastoError> {
+        let mut inner = self.lock()?;
+        inner.contents.clear();
+        inner.formats.clear();
+        inner.contents.insert(content_type, data.to_vec());
+        inner.generation += 1;
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<(), PastoError> {
+        let mut inner = self.lock()?;
+        inner.contents.clear();
+        inner.formats.clear();
+        inner.generation += 1;
+        Ok(())
+    }
+
+    fn change_token(&self) -> Result<Option<u64>, PastoError> {
+        Ok(Some(self.lock()?.generation))
+    }
+
+    fn has_format(&self, format: &str) -> Result<bool, PastoError> {
+        Ok(self.lock()?.formats.contains(format))
+    }
+
+    fn get_primary(&self) -> Result<Option<Vec<u8>>, PastoError> {
+        Ok(self.lock()?.primary.clone())
+    }
+
+    fn set_primary(&self, data: &[u8]) -> Result<(), PastoError> {
+        self.lock()?.primary = Some(data.to_vec());
+        Ok(())
+    }
+}
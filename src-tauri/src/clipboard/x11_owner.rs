@@ -0,0 +1,786 @@
+use super::ContentType;
+use crate::watcher::Selection;
+use std::collections::{HashMap, VecDeque};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tracing::{debug, info_span, warn};
+use x11rb::connection::{Connection, RequestConnection};
+use x11rb::protocol::xproto::{
+    Atom, AtomEnum, ChangeWindowAttributesAux, ClientMessageEvent, ConnectionExt as _,
+    CreateWindowAux, EventMask, PropMode, Property, SelectionNotifyEvent, SelectionRequestEvent,
+    Timestamp, Window, WindowClass, SELECTION_NOTIFY_EVENT,
+};
+use x11rb::protocol::Event;
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+use x11rb::{COPY_FROM_PARENT, CURRENT_TIME, NONE};
+
+// 退出时等待桌面剪贴板管理器（CLIPBOARD_MANAGER）取走内容的最长时间
+const HANDOFF_TIMEOUT: Duration = Duration::from_secs(2);
+// 等待取得剪贴板所有权的最长时间
+const ACQUIRE_TIMEOUT: Duration = Duration::from_secs(1);
+// 单次 ChangeProperty 写入的上限，超过时改用 INCR 分段传输
+const MAX_CHUNK_BYTES: usize = 256 * 1024;
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        CLIPBOARD,
+        PRIMARY,
+        CLIPBOARD_MANAGER,
+        SAVE_TARGETS,
+        TARGETS,
+        MULTIPLE,
+        TIMESTAMP,
+        INCR,
+        ATOM_PAIR,
+        UTF8_STRING,
+        TEXT,
+        TEXT_PLAIN_UTF8: b"text/plain;charset=utf-8",
+        TEXT_PLAIN: b"text/plain",
+        TEXT_HTML: b"text/html",
+        PASTO_WAKE,
+        PASTO_TIMESTAMP,
+        PASTO_SAVE,
+    }
+}
+
+enum Command {
+    Set {
+        selection: Atom,
+        content_type: ContentType,
+        data: Vec<u8>,
+        done: Sender<Result<(), String>>,
+    },
+    Clear,
+    // 把内容交给剪贴板管理器后结束线程
+    Shutdown,
+}
+
+// X11 上剪贴板内容属于设置它的客户端，进程内的 Clipboard 一旦释放内容就会消失。
+// 这里用一个专门的线程持有 CLIPBOARD / PRIMARY 所有权并响应其他程序的粘贴请求，
+// 退出时通过 SAVE_TARGETS 把 CLIPBOARD 内容交给桌面剪贴板管理器
+pub struct X11Owner {
+    conn: Arc<RustConnection>,
+    window: Window,
+    atoms: Atoms,
+    commands: Mutex<Sender<Command>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl X11Owner {
+    pub fn new() -> Result<Self, String> {
+        let (conn, screen_num) =
+            x11rb::connect(None).map_err(|e| format!("Failed to connect to X server: {}", e))?;
+        let conn = Arc::new(conn);
+        let root = conn.setup().roots[screen_num].root;
+        let window = conn
+            .generate_id()
+            .map_err(|e| format!("Failed to allocate window id: {}", e))?;
+        conn.create_window(
+            COPY_FROM_PARENT as u8,
+            window,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            COPY_FROM_PARENT,
+            &CreateWindowAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+        )
+        .map_err(|e| format!("Failed to create window: {}", e))?;
+        let atoms = Atoms::new(conn.as_ref())
+            .map_err(|e| format!("Failed to intern atoms: {}", e))?
+            .reply()
+            .map_err(|e| format!("Failed to intern atoms: {}", e))?;
+        conn.flush()
+            .map_err(|e| format!("Failed to flush X connection: {}", e))?;
+
+        let (tx, rx) = mpsc::channel();
+        let mut owner = Owner {
+            conn: conn.clone(),
+            window,
+            atoms,
+            commands: rx,
+            contents: HashMap::new(),
+            pending: VecDeque::new(),
+            transfers: HashMap::new(),
+            max_chunk: (conn.maximum_request_bytes() / 2).min(MAX_CHUNK_BYTES),
+            handoff_done: false,
+        };
+        let thread = thread::spawn(move || info_span!("clipboard").in_scope(|| owner.run()));
+
+        Ok(Self {
+            conn,
+            window,
+            atoms,
+            commands: Mutex::new(tx),
+            thread: Mutex::new(Some(thread)),
+        })
+    }
+
+    // 等到真正取得选区所有权后才返回
+    pub fn set(
+        &self,
+        selection: Selection,
+        content_type: ContentType,
+        data: Vec<u8>,
+    ) -> Result<(), String> {
+        let selection = match selection {
+            Selection::Clipboard => self.atoms.CLIPBOARD,
+            Selection::Primary => self.atoms.PRIMARY,
+        };
+        let (done, result) = mpsc::channel();
+        self.send(Command::Set {
+            selection,
+            content_type,
+            data,
+            done,
+        })?;
+        result
+            .recv_timeout(ACQUIRE_TIMEOUT)
+            .map_err(|_| "Timed out acquiring clipboard ownership".to_string())?
+    }
+
+    pub fn clear(&self) -> Result<(), String> {
+        self.send(Command::Clear)
+    }
+
+    // 交接内容并等待持有线程结束，重复调用无副作用
+    pub fn shutdown(&self) {
+        let thread = match self.thread.lock() {
+            Ok(mut thread) => thread.take(),
+            Err(_) => None,
+        };
+        let Some(thread) = thread else {
+            return;
+        };
+        if let Err(e) = self.send(Command::Shutdown) {
+            warn!("{}", e);
+            return;
+        }
+        if thread.join().is_err() {
+            warn!("Clipboard owner thread panicked");
+        }
+    }
+
+    // 发出命令后向自己的窗口发送 ClientMessage，唤醒阻塞在 wait_for_event 的持有线程
+    fn send(&self, command: Command) -> Result<(), String> {
+        match self.commands.lock() {
+            Ok(commands) => commands
+                .send(command)
+                .map_err(|_| "Clipboard owner thread has stopped".to_string())?,
+            Err(e) => return Err(format!("Failed to lock clipboard owner: {}", e)),
+        }
+        let event = ClientMessageEvent::new(32, self.window, self.atoms.PASTO_WAKE, [0u32; 5]);
+        self.conn
+            .send_event(false, self.window, EventMask::NO_EVENT, event)
+            .map_err(|e| format!("Failed to wake clipboard owner: {}", e))?;
+        self.conn
+            .flush()
+            .map_err(|e| format!("Failed to flush X connection: {}", e))
+    }
+}
+
+impl Drop for X11Owner {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+struct Contents {
+    content_type: ContentType,
+    data: Vec<u8>,
+    time: Timestamp,
+}
+
+struct PendingSet {
+    selection: Atom,
+    content_type: ContentType,
+    data: Vec<u8>,
+    done: Sender<Result<(), String>>,
+}
+
+// INCR 分段传输中的请求
+struct Transfer {
+    target: Atom,
+    data: Arc<Vec<u8>>,
+    offset: usize,
+}
+
+struct Owner {
+    conn: Arc<RustConnection>,
+    window: Window,
+    atoms: Atoms,
+    commands: Receiver<Command>,
+    // 按选区保存当前持有的内容
+    contents: HashMap<Atom, Contents>,
+    // 等待服务器时间戳以便设置所有权的内容，每个写入请求对应一次时间戳
+    pending: VecDeque<PendingSet>,
+    transfers: HashMap<(Window, Atom), Transfer>,
+    max_chunk: usize,
+    handoff_done: bool,
+}
+
+impl Owner {
+    fn run(&mut self) {
+        loop {
+            let event = match self.conn.wait_for_event() {
+                Ok(event) => event,
+                Err(e) => {
+                    warn!("X connection error in clipboard owner: {}", e);
+                    return;
+                }
+            };
+            let shutdown = match self.handle_event(event) {
+                Ok(shutdown) => shutdown,
+                Err(e) => {
+                    warn!("{}", e);
+                    false
+                }
+            };
+            if shutdown {
+                self.hand_off();
+                return;
+            }
+        }
+    }
+
+    // 返回 true 表示收到了退出命令
+    fn handle_event(&mut self, event: Event) -> Result<bool, String> {
+        match event {
+            Event::ClientMessage(event) if event.type_ == self.atoms.PASTO_WAKE => {
+                return self.handle_commands();
+            }
+            Event::PropertyNotify(event)
+                if event.window == self.window && event.atom == self.atoms.PASTO_TIMESTAMP =>
+            {
+                self.acquire(event.time)?;
+            }
+            Event::PropertyNotify(event) if event.state == Property::DELETE => {
+                self.continue_transfer(event.window, event.atom)?;
+            }
+            Event::SelectionRequest(event) => self.handle_request(event)?,
+            Event::SelectionClear(event) => {
+                self.contents.remove(&event.selection);
+            }
+            Event::SelectionNotify(event) if event.selection == self.atoms.CLIPBOARD_MANAGER => {
+                self.handoff_done = true;
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn handle_commands(&mut self) -> Result<bool, String> {
+        while let Ok(command) = self.commands.try_recv() {
+            match command {
+                Command::Set {
+                    selection,
+                    content_type,
+                    data,
+                    done,
+                } => {
+                    // 用一次空的属性追加换取服务器时间戳，ICCCM 不建议用 CurrentTime 设置所有权
+                    self.conn
+                        .change_property8(
+                            PropMode::APPEND,
+                            self.window,
+                            self.atoms.PASTO_TIMESTAMP,
+                            AtomEnum::INTEGER,
+                            &[],
+                        )
+                        .map_err(|e| format!("Failed to request server time: {}", e))?;
+                    self.flush()?;
+                    self.pending.push_back(PendingSet {
+                        selection,
+                        content_type,
+                        data,
+                        done,
+                    });
+                }
+                Command::Clear => {
+                    if let Some(contents) = self.contents.remove(&self.atoms.CLIPBOARD) {
+                        self.conn
+                            .set_selection_owner(NONE, self.atoms.CLIPBOARD, contents.time)
+                            .map_err(|e| format!("Failed to release clipboard: {}", e))?;
+                        self.flush()?;
+                    }
+                }
+                Command::Shutdown => return Ok(true),
+            }
+        }
+        Ok(false)
+    }
+
+    fn acquire(&mut self, time: Timestamp) -> Result<(), String> {
+        let Some(PendingSet {
+            selection,
+            content_type,
+            data,
+            done,
+        }) = self.pending.pop_front()
+        else {
+            return Ok(());
+        };
+        let result = self.set_owner(selection, time);
+        if result.is_ok() {
+            self.contents.insert(
+                selection,
+                Contents {
+                    content_type,
+                    data,
+                    time,
+                },
+            );
+        }
+        let _ = done.send(result);
+        Ok(())
+    }
+
+    fn set_owner(&self, selection: Atom, time: Timestamp) -> Result<(), String> {
+        self.conn
+            .set_selection_owner(self.window, selection, time)
+            .map_err(|e| format!("Failed to set clipboard owner: {}", e))?;
+        let owner = self
+            .conn
+            .get_selection_owner(selection)
+            .map_err(|e| format!("Failed to query clipboard owner: {}", e))?
+            .reply()
+            .map_err(|e| format!("Failed to query clipboard owner: {}", e))?
+            .owner;
+        if owner == self.window {
+            Ok(())
+        } else {
+            Err("Another client took clipboard ownership".to_string())
+        }
+    }
+
+    fn handle_request(&mut self, request: SelectionRequestEvent) -> Result<(), String> {
+        // 旧客户端可能不指定属性，此时按 ICCCM 用 target 作为属性名
+        let property = if request.property == NONE {
+            request.target
+        } else {
+            request.property
+        };
+        let owns = self.contents.get(&request.selection).is_some_and(|contents| {
+            request.time == CURRENT_TIME || request.time >= contents.time
+        });
+        let converted = owns
+            && self.convert(request.selection, request.requestor, request.target, property)?;
+
+        let notify = SelectionNotifyEvent {
+            response_type: SELECTION_NOTIFY_EVENT,
+            sequence: 0,
+            time: request.time,
+            requestor: request.requestor,
+            selection: request.selection,
+            target: request.target,
+            property: if converted { property } else { NONE },
+        };
+        self.conn
+            .send_event(false, request.requestor, EventMask::NO_EVENT, notify)
+            .map_err(|e| format!("Failed to answer selection request: {}", e))?;
+        self.flush()
+    }
+
+    // 把 target 格式的内容写入请求方窗口的 property，返回是否支持该格式
+    fn convert(
+        &mut self,
+        selection: Atom,
+        requestor: Window,
+        target: Atom,
+        property: Atom,
+    ) -> Result<bool, String> {
+        let Some(contents) = self.contents.get(&selection) else {
+            return Ok(false);
+        };
+        let atoms = self.atoms;
+
+        if target == atoms.TARGETS {
+            let mut targets = vec![atoms.TARGETS, atoms.TIMESTAMP, atoms.MULTIPLE];
+            targets.extend(self.targets_for(contents.content_type));
+            self.conn
+                .change_property32(PropMode::REPLACE, requestor, property, AtomEnum::ATOM, &targets)
+                .map_err(|e| format!("Failed to write TARGETS: {}", e))?;
+            return Ok(true);
+        }
+        if target == atoms.TIMESTAMP {
+            self.conn
+                .change_property32(
+                    PropMode::REPLACE,
+                    requestor,
+                    property,
+                    AtomEnum::INTEGER,
+                    &[contents.time],
+                )
+                .map_err(|e| format!("Failed to write TIMESTAMP: {}", e))?;
+            return Ok(true);
+        }
+        if target == atoms.MULTIPLE {
+            return self.convert_multiple(selection, requestor, property);
+        }
+        if !self.targets_for(contents.content_type).contains(&target) {
+            return Ok(false);
+        }
+
+        if contents.data.len() > self.max_chunk {
+            // 内容过大时先写入 INCR 和总长度，之后每当请求方删除属性就写入下一段
+            let data = Arc::new(contents.data.clone());
+            self.conn
+                .change_window_attributes(
+                    requestor,
+                    &ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+                )
+                .map_err(|e| format!("Failed to watch requestor window: {}", e))?;
+            self.conn
+                .change_property32(
+                    PropMode::REPLACE,
+                    requestor,
+                    property,
+                    atoms.INCR,
+                    &[data.len() as u32],
+                )
+                .map_err(|e| format!("Failed to start INCR transfer: {}", e))?;
+            self.transfers.insert(
+                (requestor, property),
+                Transfer {
+                    target,
+                    data,
+                    offset: 0,
+                },
+            );
+        } else {
+            self.conn
+                .change_property8(PropMode::REPLACE, requestor, property, target, &contents.data)
+                .map_err(|e| format!("Failed to write clipboard contents: {}", e))?;
+        }
+        Ok(true)
+    }
+
+    // MULTIPLE 请求的属性里是若干 (target, property) 对，逐个转换，失败的那一对把 property 置为 None
+    fn convert_multiple(
+        &mut self,
+        selection: Atom,
+        requestor: Window,
+        property: Atom,
+    ) -> Result<bool, String> {
+        let reply = self
+            .conn
+            .get_property(false, requestor, property, self.atoms.ATOM_PAIR, 0, u32::MAX / 4)
+            .map_err(|e| format!("Failed to read MULTIPLE request: {}", e))?
+            .reply()
+            .map_err(|e| format!("Failed to read MULTIPLE request: {}", e))?;
+        let Some(pairs) = reply.value32() else {
+            return Ok(false);
+        };
+        let mut pairs: Vec<Atom> = pairs.collect();
+        for pair in pairs.chunks_mut(2) {
+            if let [target, pair_property] = pair {
+                if *target == self.atoms.MULTIPLE
+                    || !self.convert(selection, requestor, *target, *pair_property)?
+                {
+                    *pair_property = NONE;
+                }
+            }
+        }
+        self.conn
+            .change_property32(PropMode::REPLACE, requestor, property, self.atoms.ATOM_PAIR, &pairs)
+            .map_err(|e| format!("Failed to answer MULTIPLE request: {}", e))?;
+        Ok(true)
+    }
+
+    fn continue_transfer(&mut self, requestor: Window, property: Atom) -> Result<(), String> {
+        let Some(transfer) = self.transfers.get_mut(&(requestor, property)) else {
+            return Ok(());
+        };
+        let end = (transfer.offset + self.max_chunk).min(transfer.data.len());
+        let chunk = &transfer.data[transfer.offset..end];
+        let finished = chunk.is_empty();
+        self.conn
+            .change_property8(PropMode::REPLACE, requestor, property, transfer.target, chunk)
+            .map_err(|e| format!("Failed to write INCR chunk: {}", e))?;
+        transfer.offset = end;
+
+        // 写入长度为 0 的一段表示传输结束
+        if finished {
+            self.transfers.remove(&(requestor, property));
+            if !self.transfers.keys().any(|(window, _)| *window == requestor) {
+                self.conn
+                    .change_window_attributes(
+                        requestor,
+                        &ChangeWindowAttributesAux::new().event_mask(EventMask::NO_EVENT),
+                    )
+                    .map_err(|e| format!("Failed to unwatch requestor window: {}", e))?;
+            }
+        }
+        self.flush()
+    }
+
+    fn targets_for(&self, content_type: ContentType) -> Vec<Atom> {
+        match content_type {
+            ContentType::Text => vec![
+                self.atoms.UTF8_STRING,
+                self.atoms.TEXT_PLAIN_UTF8,
+                self.atoms.TEXT_PLAIN,
+                self.atoms.TEXT,
+                AtomEnum::STRING.into(),
+            ],
+            ContentType::Html => vec![self.atoms.TEXT_HTML],
+        }
+    }
+
+    // 仍持有 CLIPBOARD 内容且存在剪贴板管理器时，请求它保存内容，并在等待期间继续响应它的读取请求
+    fn hand_off(&mut self) {
+        let Some(time) = self
+            .contents
+            .get(&self.atoms.CLIPBOARD)
+            .map(|contents| contents.time)
+        else {
+            return;
+        };
+        let manager = self
+            .conn
+            .get_selection_owner(self.atoms.CLIPBOARD_MANAGER)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map(|reply| reply.owner)
+            .unwrap_or(NONE);
+        if manager == NONE {
+            debug!("No clipboard manager running, clipboard contents will be lost");
+            return;
+        }
+
+        let requested = self
+            .conn
+            .convert_selection(
+                self.window,
+                self.atoms.CLIPBOARD_MANAGER,
+                self.atoms.SAVE_TARGETS,
+                self.atoms.PASTO_SAVE,
+                time,
+            )
+            .map_err(|e| format!("Failed to request SAVE_TARGETS: {}", e))
+            .and_then(|_| self.flush());
+        if let Err(e) = requested {
+            warn!("{}", e);
+            return;
+        }
+
+        let deadline = Instant::now() + HANDOFF_TIMEOUT;
+        while !self.handoff_done && Instant::now() < deadline {
+            match self.conn.poll_for_event() {
+                Ok(Some(event)) => {
+                    if let Err(e) = self.handle_event(event) {
+                        warn!("{}", e);
+                    }
+                }
+                Ok(None) => thread::sleep(Duration::from_millis(5)),
+                Err(e) => {
+                    warn!("X connection error during handoff: {}", e);
+                    return;
+                }
+            }
+        }
+        if self.handoff_done {
+            debug!("Handed clipboard contents to the clipboard manager");
+        } else {
+            warn!("Clipboard manager did not confirm SAVE_TARGETS in time");
+        }
+    }
+
+    fn flush(&self) -> Result<(), String> {
+        self.conn
+            .flush()
+            .map_err(|e| format!("Failed to flush X connection: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xvfb::Xvfb;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+    // 另一个 X 客户端，用来读取选区或扮演剪贴板管理器
+    struct Client {
+        conn: RustConnection,
+        window: Window,
+        atoms: Atoms,
+    }
+
+    impl Client {
+        fn connect() -> Self {
+            let (conn, screen_num) = x11rb::connect(None).expect("failed to connect to Xvfb");
+            let root = conn.setup().roots[screen_num].root;
+            let window = conn.generate_id().unwrap();
+            conn.create_window(
+                COPY_FROM_PARENT as u8,
+                window,
+                root,
+                0,
+                0,
+                1,
+                1,
+                0,
+                WindowClass::INPUT_ONLY,
+                COPY_FROM_PARENT,
+                &CreateWindowAux::new(),
+            )
+            .unwrap();
+            let atoms = Atoms::new(&conn).unwrap().reply().unwrap();
+            Self {
+                conn,
+                window,
+                atoms,
+            }
+        }
+
+        // 以 UTF8_STRING 读取选区内容，没有所有者或超时返回 None
+        fn read(&self, selection: Atom) -> Option<Vec<u8>> {
+            let property = self.atoms.PASTO_SAVE;
+            self.conn
+                .convert_selection(self.window, selection, self.atoms.UTF8_STRING, property, CURRENT_TIME)
+                .unwrap();
+            self.conn.flush().unwrap();
+
+            let deadline = Instant::now() + READ_TIMEOUT;
+            let notify = loop {
+                match self.conn.poll_for_event().unwrap() {
+                    Some(Event::SelectionNotify(notify)) => break notify,
+                    Some(_) => continue,
+                    None if Instant::now() >= deadline => return None,
+                    None => thread::sleep(Duration::from_millis(5)),
+                }
+            };
+            if notify.property == NONE {
+                return None;
+            }
+            let reply = self
+                .conn
+                .get_property(true, self.window, property, AtomEnum::ANY, 0, u32::MAX / 4)
+                .unwrap()
+                .reply()
+                .unwrap();
+            Some(reply.value)
+        }
+
+        fn reply(&self, request: &SelectionRequestEvent, property: Atom) {
+            let notify = SelectionNotifyEvent {
+                response_type: SELECTION_NOTIFY_EVENT,
+                sequence: 0,
+                time: request.time,
+                requestor: request.requestor,
+                selection: request.selection,
+                target: request.target,
+                property,
+            };
+            self.conn
+                .send_event(false, request.requestor, EventMask::NO_EVENT, notify)
+                .unwrap();
+            self.conn.flush().unwrap();
+        }
+    }
+
+    // 最小的剪贴板管理器：收到 SAVE_TARGETS 时读出 CLIPBOARD 内容并接管所有权，之后响应读取请求
+    fn run_manager(ready: Sender<()>, stop: Arc<AtomicBool>) {
+        let manager = Client::connect();
+        let atoms = manager.atoms;
+        manager
+            .conn
+            .set_selection_owner(manager.window, atoms.CLIPBOARD_MANAGER, CURRENT_TIME)
+            .unwrap();
+        manager.conn.flush().unwrap();
+        ready.send(()).unwrap();
+
+        let mut saved: Option<Vec<u8>> = None;
+        while !stop.load(Ordering::SeqCst) {
+            let Some(event) = manager.conn.poll_for_event().unwrap() else {
+                thread::sleep(Duration::from_millis(5));
+                continue;
+            };
+            let Event::SelectionRequest(request) = event else {
+                continue;
+            };
+            if request.selection == atoms.CLIPBOARD_MANAGER && request.target == atoms.SAVE_TARGETS {
+                saved = manager.read(atoms.CLIPBOARD);
+                manager
+                    .conn
+                    .set_selection_owner(manager.window, atoms.CLIPBOARD, CURRENT_TIME)
+                    .unwrap();
+                let property = if saved.is_some() { request.property } else { NONE };
+                manager.reply(&request, property);
+            } else if request.selection == atoms.CLIPBOARD && request.target == atoms.UTF8_STRING {
+                let property = match &saved {
+                    Some(data) => {
+                        manager
+                            .conn
+                            .change_property8(
+                                PropMode::REPLACE,
+                                request.requestor,
+                                request.property,
+                                atoms.UTF8_STRING,
+                                data,
+                            )
+                            .unwrap();
+                        request.property
+                    }
+                    None => NONE,
+                };
+                manager.reply(&request, property);
+            } else {
+                manager.reply(&request, NONE);
+            }
+        }
+    }
+
+    // 需要 Xvfb：cargo test -- --ignored x11
+    #[test]
+    #[ignore = "needs Xvfb"]
+    fn x11_hand_off_keeps_clipboard_after_owner_exits() {
+        let _xvfb = Xvfb::start();
+        let (ready, started) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let manager = thread::spawn({
+            let stop = stop.clone();
+            move || run_manager(ready, stop)
+        });
+        started.recv_timeout(READ_TIMEOUT).expect("clipboard manager did not start");
+
+        let owner = X11Owner::new().unwrap();
+        owner
+            .set(Selection::Clipboard, ContentType::Text, b"handed off".to_vec())
+            .unwrap();
+        let reader = Client::connect();
+        assert_eq!(reader.read(reader.atoms.CLIPBOARD).as_deref(), Some(&b"handed off"[..]));
+
+        // 释放时交接给剪贴板管理器，之后由管理器继续提供内容
+        drop(owner);
+        assert_eq!(reader.read(reader.atoms.CLIPBOARD).as_deref(), Some(&b"handed off"[..]));
+
+        stop.store(true, Ordering::SeqCst);
+        manager.join().unwrap();
+    }
+
+    #[test]
+    #[ignore = "needs Xvfb"]
+    fn x11_owner_exits_without_clipboard_manager() {
+        let _xvfb = Xvfb::start();
+        let owner = X11Owner::new().unwrap();
+        owner
+            .set(Selection::Clipboard, ContentType::Text, b"lost".to_vec())
+            .unwrap();
+
+        // 没有剪贴板管理器时不等待交接，内容随所有者一起消失
+        let started = Instant::now();
+        drop(owner);
+        assert!(started.elapsed() < HANDOFF_TIMEOUT);
+        let reader = Client::connect();
+        assert_eq!(reader.read(reader.atoms.CLIPBOARD), None);
+    }
+}
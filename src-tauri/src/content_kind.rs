@@ -0,0 +1,372 @@
+use serde::{Deserialize, Serialize};
+
+// 剪贴板文本的内容类型
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentKind {
+    Url,
+    Email,
+    PhoneNumber,
+    FilePath,
+    Json,
+    Code,
+    Color,
+    OneTimeCode,
+    Prose,
+}
+
+impl ContentKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentKind::Url => "url",
+            ContentKind::Email => "email",
+            ContentKind::PhoneNumber => "phone_number",
+            ContentKind::FilePath => "file_path",
+            ContentKind::Json => "json",
+            ContentKind::Code => "code",
+            ContentKind::Color => "color",
+            ContentKind::OneTimeCode => "one_time_code",
+            ContentKind::Prose => "prose",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Some(match value {
+            "url" => ContentKind::Url,
+            "email" => ContentKind::Email,
+            "phone_number" => ContentKind::PhoneNumber,
+            "file_path" => ContentKind::FilePath,
+            "json" => ContentKind::Json,
+            "code" => ContentKind::Code,
+            "color" => ContentKind::Color,
+            "one_time_code" => ContentKind::OneTimeCode,
+            "prose" => ContentKind::Prose,
+            _ => return None,
+        })
+    }
+}
+
+// 检测结果，代码类内容附带猜测的语言
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ContentClass {
+    pub kind: ContentKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+impl ContentClass {
+    fn of(kind: ContentKind) -> Self {
+        Self {
+            kind,
+            language: None,
+        }
+    }
+}
+
+// 各语言的特征片段，命中越多越可能是该语言
+const LANGUAGE_SIGNALS: &[(&str, &[&str])] = &[
+    ("rust", &["fn ", "let mut ", "impl ", "pub fn", "use std::", "-> Result<", "match ", "&self", "::new("]),
+    ("python", &["def ", "import ", "elif ", "self.", "print(", "from ", "__init__", "None:", "):\n"]),
+    ("typescript", &["interface ", ": string", ": number", "export type ", "as const", "<T>"]),
+    ("javascript", &["const ", "function ", "=> ", "console.log", "require(", "export default", "let "]),
+    ("go", &["func ", "package ", ":= ", "fmt.", "go func", "err != nil"]),
+    ("java", &["public class ", "System.out", "private static ", "void main(", "@Override"]),
+    ("c", &["#include ", "int main(", "printf(", "malloc(", "->"]),
+    ("cpp", &["std::", "#include <iostream>", "template<", "nullptr", "cout <<"]),
+    ("shell", &["#!/bin/", "sudo ", "echo ", "export ", "| grep", "apt ", "&& ", "$("]),
+    ("sql", &["SELECT ", "FROM ", "WHERE ", "INSERT INTO", "UPDATE ", "CREATE TABLE", "JOIN "]),
+    ("html", &["<!DOCTYPE", "<html", "<div", "</", "<span", "class=\""]),
+    ("css", &["{\n", ": ", "px;", "color:", "margin:", "display:"]),
+];
+
+// 至少命中这么多特征才认定为某种语言
+const MIN_LANGUAGE_SIGNALS: usize = 2;
+
+// 按固定顺序判断，先判断格式严格的类型，最后退回到普通文本
+pub fn detect(text: &str) -> ContentClass {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return ContentClass::of(ContentKind::Prose);
+    }
+
+    if !trimmed.contains('\n') {
+        if is_color(trimmed) {
+            return ContentClass::of(ContentKind::Color);
+        }
+        if is_url(trimmed) {
+            return ContentClass::of(ContentKind::Url);
+        }
+        if is_email(trimmed) {
+            return ContentClass::of(ContentKind::Email);
+        }
+        if is_one_time_code(trimmed) {
+            return ContentClass::of(ContentKind::OneTimeCode);
+        }
+        if is_phone_number(trimmed) {
+            return ContentClass::of(ContentKind::PhoneNumber);
+        }
+        if is_file_path(trimmed) {
+            return ContentClass::of(ContentKind::FilePath);
+        }
+    }
+
+    if is_json(trimmed) {
+        return ContentClass::of(ContentKind::Json);
+    }
+    if let Some(language) = detect_code(trimmed) {
+        return ContentClass {
+            kind: ContentKind::Code,
+            language,
+        };
+    }
+    ContentClass::of(ContentKind::Prose)
+}
+
+fn is_color(text: &str) -> bool {
+    if let Some(hex) = text.strip_prefix('#') {
+        return matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit());
+    }
+    let lower = text.to_ascii_lowercase();
+    ["rgb(", "rgba(", "hsl(", "hsla("].iter().any(|prefix| {
+        lower.strip_prefix(prefix).is_some_and(|rest| {
+            rest.ends_with(')')
+                && rest[..rest.len() - 1]
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || matches!(c, ',' | '.' | '%' | ' ' | '/'))
+        })
+    })
+}
+
+fn is_url(text: &str) -> bool {
+    if text.contains(char::is_whitespace) {
+        return false;
+    }
+    let rest = ["http://", "https://", "ftp://", "ws://", "wss://"]
+        .iter()
+        .find_map(|scheme| text.strip_prefix(scheme))
+        .or_else(|| text.strip_prefix("www."));
+    rest.is_some_and(|rest| {
+        let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+        !host.is_empty() && (host.contains('.') || host.starts_with("localhost"))
+    })
+}
+
+fn is_email(text: &str) -> bool {
+    let text = text.strip_prefix("mailto:").unwrap_or(text);
+    let Some((local, domain)) = text.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && local
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '%' | '+' | '-'))
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && domain
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-'))
+}
+
+// 4~8 位数字的验证码，允许中间一个空格或短横线分组（如 "123 456"）
+fn is_one_time_code(text: &str) -> bool {
+    let digits: String = match text.split_once([' ', '-']) {
+        Some((left, right)) if !left.is_empty() && !right.is_empty() => format!("{}{}", left, right),
+        Some(_) => return false,
+        None => text.to_string(),
+    };
+    (4..=8).contains(&digits.len()) && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+// 7~15 位数字，可带 + 前缀及常见分隔符；排除 2024-01-31 这样的日期
+fn is_phone_number(text: &str) -> bool {
+    if is_date(text) {
+        return false;
+    }
+    let body = text.strip_prefix('+').unwrap_or(text);
+    if !body
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, ' ' | '-' | '(' | ')' | '.'))
+    {
+        return false;
+    }
+    let digits = body.chars().filter(|c| c.is_ascii_digit()).count();
+    (7..=15).contains(&digits)
+}
+
+fn is_date(text: &str) -> bool {
+    let parts: Vec<&str> = text.split(['-', '/', '.']).collect();
+    match parts.as_slice() {
+        [a, b, c] => {
+            let lens = (a.len(), b.len(), c.len());
+            (lens == (4, 2, 2) || lens == (2, 2, 4))
+                && parts.iter().all(|part| part.chars().all(|c| c.is_ascii_digit()))
+        }
+        _ => false,
+    }
+}
+
+fn is_file_path(text: &str) -> bool {
+    if text.starts_with("file://") {
+        return true;
+    }
+    let unix = ["/", "~/", "./", "../"]
+        .iter()
+        .any(|prefix| text.starts_with(prefix))
+        && text.len() > 1
+        && !text.starts_with("//");
+    let bytes = text.as_bytes();
+    let windows_drive = bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes[2] == b'\\' || bytes[2] == b'/');
+    let unc = text.starts_with("\\\\") && text.len() > 2;
+    unix || windows_drive || unc
+}
+
+fn is_json(text: &str) -> bool {
+    (text.starts_with('{') || text.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(text).is_ok()
+}
+
+// 识别代码：命中足够多某语言特征时返回该语言；
+// 否则按标点和缩进等通用特征判断，返回 Some(None) 表示是代码但语言未知
+fn detect_code(text: &str) -> Option<Option<String>> {
+    let best = LANGUAGE_SIGNALS
+        .iter()
+        .map(|(language, signals)| {
+            let hits = signals.iter().filter(|signal| text.contains(*signal)).count();
+            (hits, *language)
+        })
+        .max_by_key(|(hits, _)| *hits);
+    if let Some((hits, language)) = best {
+        if hits >= MIN_LANGUAGE_SIGNALS && looks_structured(text) {
+            return Some(Some(language.to_string()));
+        }
+    }
+
+    let lines: Vec<&str> = text.lines().filter(|line| !line.trim().is_empty()).collect();
+    if lines.len() < 2 {
+        return None;
+    }
+    let code_lines = lines
+        .iter()
+        .filter(|line| {
+            let line = line.trim_end();
+            line.ends_with(';')
+                || line.ends_with('{')
+                || line.ends_with('}')
+                || line.ends_with(')')
+                || line.starts_with("    ")
+                || line.starts_with('\t')
+        })
+        .count();
+    if code_lines * 2 >= lines.len() {
+        Some(None)
+    } else {
+        None
+    }
+}
+
+// 普通句子里也可能出现 "from "、"let " 等词，要求同时带有代码常见的符号
+fn looks_structured(text: &str) -> bool {
+    let symbols = text
+        .chars()
+        .filter(|c| matches!(c, '{' | '}' | '(' | ')' | ';' | '=' | '<' | '>' | ':' | '[' | ']'))
+        .count();
+    symbols * 20 >= text.chars().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(kind: ContentKind) -> ContentClass {
+        ContentClass::of(kind)
+    }
+
+    fn code(language: Option<&str>) -> ContentClass {
+        ContentClass {
+            kind: ContentKind::Code,
+            language: language.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn detects_content_classes() {
+        let cases: &[(&str, ContentClass)] = &[
+            // 链接
+            ("https://example.com/docs?page=2#intro", kind(ContentKind::Url)),
+            ("http://localhost:8080/api", kind(ContentKind::Url)),
+            ("www.example.org", kind(ContentKind::Url)),
+            ("  wss://example.com/socket\n", kind(ContentKind::Url)),
+            // 邮箱
+            ("someone.name+tag@example.co.uk", kind(ContentKind::Email)),
+            ("mailto:team@example.com", kind(ContentKind::Email)),
+            // 颜色、验证码、电话号码
+            ("#1e90ff", kind(ContentKind::Color)),
+            ("rgba(30, 144, 255, 0.5)", kind(ContentKind::Color)),
+            ("483 920", kind(ContentKind::OneTimeCode)),
+            ("+1 (555) 123-4567", kind(ContentKind::PhoneNumber)),
+            // 路径
+            ("/usr/local/bin/pastod", kind(ContentKind::FilePath)),
+            ("~/.config/pasto/settings.json", kind(ContentKind::FilePath)),
+            ("C:\\Users\\me\\Desktop", kind(ContentKind::FilePath)),
+            ("file:///tmp/report.pdf", kind(ContentKind::FilePath)),
+            // JSON
+            ("{\"name\": \"pasto\", \"version\": 1}", kind(ContentKind::Json)),
+            ("[1, 2, 3]", kind(ContentKind::Json)),
+            // 代码
+            (
+                "fn main() {\n    let mut count = 0;\n    count += 1;\n}",
+                code(Some("rust")),
+            ),
+            (
+                "def greet(name):\n    print(f\"hello {name}\")\n    return None",
+                code(Some("python")),
+            ),
+            ("SELECT id, name\nFROM users\nWHERE id IN (1, 2);", code(Some("sql"))),
+            ("if (x) {\n    y();\n}", code(None)),
+            // 普通文本
+            ("Meeting moved to Thursday afternoon.", kind(ContentKind::Prose)),
+            (
+                "Let me know where you are from and what you think.\nThanks!",
+                kind(ContentKind::Prose),
+            ),
+            ("", kind(ContentKind::Prose)),
+            ("   \n  ", kind(ContentKind::Prose)),
+            // 容易混淆的输入
+            ("2024-01-31", kind(ContentKind::Prose)),
+            ("{not json", kind(ContentKind::Prose)),
+            ("//comment-like", kind(ContentKind::Prose)),
+            ("example.com", kind(ContentKind::Prose)),
+            ("user@localhost", kind(ContentKind::Prose)),
+            ("#zzzzzz", kind(ContentKind::Prose)),
+            ("123", kind(ContentKind::Prose)),
+            ("see https://example.com for details", kind(ContentKind::Prose)),
+            ("https://example.com\nhttps://example.org", kind(ContentKind::Prose)),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(&detect(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn kind_names_round_trip() {
+        for kind in [
+            ContentKind::Url,
+            ContentKind::Email,
+            ContentKind::PhoneNumber,
+            ContentKind::FilePath,
+            ContentKind::Json,
+            ContentKind::Code,
+            ContentKind::Color,
+            ContentKind::OneTimeCode,
+            ContentKind::Prose,
+        ] {
+            assert_eq!(ContentKind::parse(kind.as_str()), Some(kind));
+        }
+        assert_eq!(ContentKind::parse("unknown"), None);
+    }
+}
@@ -0,0 +1,567 @@
+use crate::ble;
+use crate::clipboard::{ClipboardContent, ClipboardManager};
+use crate::content_kind::ContentClass;
+use crate::history::{self, HistoryStore};
+use crate::hlc;
+use crate::logging::{self, LogLevel};
+use crate::settings::{Settings, SettingsStore};
+use crate::sync::{self, Outgoing};
+use crate::transform::Direction;
+use crate::watcher::{self, Selection, WatchOptions};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, Notify};
+use tracing::{error, info, warn};
+
+// 等待 receive 请求的客户端来不及读取时最多缓存的条数
+const RECEIVED_CHANNEL_CAPACITY: usize = 16;
+// 历史记录定期清理的间隔
+const HISTORY_COMPACTION_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+// pastod 的配置文件，未填写的字段使用默认值。
+// 同步规则等设置与桌面版相同，保存在 settings 指向的文件中
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct DaemonConfig {
+    // 设置文件，格式与桌面版的 settings.json 相同
+    pub settings: PathBuf,
+    // 剪贴板历史数据库
+    pub history: PathBuf,
+    // 本地控制 socket
+    pub socket: PathBuf,
+    // 滚动日志文件所在目录
+    pub log_dir: PathBuf,
+    // 启动后自动连接的设备地址，断开后定期重连
+    pub connect: Option<String>,
+    pub reconnect_secs: u64,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            settings: config_dir().join("settings.json"),
+            history: data_dir().join("history.db"),
+            socket: default_socket_path(),
+            log_dir: state_dir().join("logs"),
+            connect: None,
+            reconnect_secs: 10,
+        }
+    }
+}
+
+impl DaemonConfig {
+    // 配置文件不存在时使用默认配置
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|e| format!("Failed to parse daemon config: {}", e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("Failed to read daemon config: {}", e)),
+        }
+    }
+
+    pub fn default_path() -> PathBuf {
+        config_dir().join("pastod.json")
+    }
+}
+
+// $XDG_CONFIG_HOME/pasto，未设置时为 ~/.config/pasto
+fn config_dir() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config").join("pasto")
+}
+
+// $XDG_DATA_HOME/pasto，未设置时为 ~/.local/share/pasto
+fn data_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", ".local/share").join("pasto")
+}
+
+// $XDG_STATE_HOME/pasto，未设置时为 ~/.local/state/pasto
+fn state_dir() -> PathBuf {
+    xdg_dir("XDG_STATE_HOME", ".local/state").join("pasto")
+}
+
+fn xdg_dir(var: &str, fallback: &str) -> PathBuf {
+    match std::env::var_os(var).filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => std::env::var_os("HOME")
+            .map(PathBuf::from)
+            .unwrap_or_default()
+            .join(fallback),
+    }
+}
+
+// $XDG_RUNTIME_DIR/pasto.sock，未设置时放在临时目录
+pub fn default_socket_path() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("pasto.sock")
+}
+
+// 控制 socket 上的请求，每行一个 JSON，如 {"cmd":"connect","address":"..."}
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum Request {
+    Status,
+    Connect { address: String },
+    // 指定 address 时先连接该设备，已连接其他设备时切换过去
+    Send {
+        text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        address: Option<String>,
+    },
+    // 已信任设备由 pastod 修改，避免命令行直接写设置文件后被 pastod 的下次保存覆盖
+    Trust {
+        address: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    },
+    // id 为地址或名称，成功时返回被移除设备的地址
+    Forget { id: String },
+    // 等待下一条收到的内容并返回 Received，指定 address 时先连接该设备
+    Receive {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        address: Option<String>,
+    },
+    Pause,
+    Resume,
+    Shutdown,
+}
+
+// 对每个请求回复一行 JSON
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Response {
+    fn ok(data: impl Serialize) -> Self {
+        match serde_json::to_value(data) {
+            Ok(data) => Self {
+                ok: true,
+                data: Some(data),
+                error: None,
+            },
+            Err(e) => Self::error(format!("Failed to serialize response: {}", e)),
+        }
+    }
+
+    fn done() -> Self {
+        Self {
+            ok: true,
+            data: None,
+            error: None,
+        }
+    }
+
+    fn error(message: String) -> Self {
+        Self {
+            ok: false,
+            data: None,
+            error: Some(message),
+        }
+    }
+
+    fn from_result(result: Result<(), String>) -> Self {
+        match result {
+            Ok(()) => Self::done(),
+            Err(e) => Self::error(e),
+        }
+    }
+}
+
+// 收到的一条内容，已按接收方向变换
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Received {
+    pub device: String,
+    pub content: String,
+    pub kind: Option<ContentClass>,
+    pub ttl_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DaemonStatus {
+    pub connected: Option<String>,
+    pub paused: bool,
+    pub backend: String,
+}
+
+struct Daemon {
+    config: DaemonConfig,
+    settings: SettingsStore,
+    history: Arc<HistoryStore>,
+    clipboard: Arc<Mutex<ClipboardManager>>,
+    paused: AtomicBool,
+    shutdown: Notify,
+    // 转发给等待 receive 请求的客户端
+    received: broadcast::Sender<Received>,
+}
+
+// 启动守护进程，收到 shutdown 请求、Ctrl-C 或 SIGTERM 后返回
+pub fn run(config_path: &Path) -> Result<(), String> {
+    let config = DaemonConfig::load(config_path)?;
+    logging::init(LogLevel::default(), Some(&config.log_dir));
+    let settings = SettingsStore::open(&config.settings)?;
+    logging::apply(&settings.get()?.logging);
+    hlc::set_node_id(&settings.get()?.node_id);
+    let history = HistoryStore::open(&config.history)?;
+    history.set_policy(settings.get()?.history)?;
+    let history = Arc::new(history);
+    history::spawn_compaction(history.clone(), HISTORY_COMPACTION_INTERVAL);
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Failed to start async runtime: {}", e))?;
+
+    let daemon = Arc::new(Daemon {
+        config,
+        settings,
+        history,
+        clipboard: Arc::new(Mutex::new(ClipboardManager::new())),
+        paused: AtomicBool::new(false),
+        shutdown: Notify::new(),
+        received: broadcast::channel(RECEIVED_CHANNEL_CAPACITY).0,
+    });
+    info!(
+        "Started with {} clipboard backend",
+        daemon.status().backend
+    );
+
+    let handle = runtime.handle().clone();
+    let watcher_daemon = daemon.clone();
+    watcher::spawn_clipboard_watcher(
+        daemon.clipboard.clone(),
+        WatchOptions {
+            watch_primary: cfg!(target_os = "linux"),
+        },
+        move |selection, content| watcher_daemon.handle_local_change(&handle, selection, content),
+    );
+
+    let result = runtime.block_on(async {
+        if let Some(address) = daemon.config.connect.clone() {
+            tokio::spawn(daemon.clone().keep_connected(address));
+        }
+        tokio::select! {
+            result = serve_control(daemon.clone()) => result,
+            _ = daemon.shutdown.notified() => Ok(()),
+            _ = tokio::signal::ctrl_c() => Ok(()),
+            _ = terminate_signal() => Ok(()),
+        }
+    });
+
+    // X11 下剪贴板内容属于本进程，退出前交给桌面剪贴板管理器保管
+    if let Ok(manager) = daemon.clipboard.lock() {
+        manager.shutdown();
+    }
+    let _ = std::fs::remove_file(&daemon.config.socket);
+    info!("Stopped");
+    result
+}
+
+impl Daemon {
+    fn settings(&self) -> Settings {
+        self.settings.get().unwrap_or_else(|e| {
+            warn!("Failed to read settings: {}", e.chain());
+            Settings::default()
+        })
+    }
+
+    fn status(&self) -> DaemonStatus {
+        let backend = match self.clipboard.lock() {
+            Ok(manager) => manager.backend_name().to_string(),
+            Err(_) => "unknown".to_string(),
+        };
+        DaemonStatus {
+            connected: ble::connected_address(),
+            paused: self.paused.load(Ordering::SeqCst),
+            backend,
+        }
+    }
+
+    async fn handle_request(self: &Arc<Self>, request: Request) -> Response {
+        match request {
+            Request::Status => Response::ok(self.status()),
+            Request::Connect { address } => Response::from_result(self.connect(address).await),
+            Request::Send { text, address } => Response::from_result(self.send_text(text, address).await),
+            Request::Trust { address, name } => Response::from_result(self.trust(&address, name)),
+            Request::Forget { id } => match self.settings.forget_device(&id) {
+                Ok(address) => Response::ok(address),
+                Err(e) => Response::error(e.chain()),
+            },
+            Request::Receive { address } => match self.receive(address).await {
+                Ok(received) => Response::ok(received),
+                Err(e) => Response::error(e),
+            },
+            Request::Pause | Request::Resume => {
+                let paused = matches!(request, Request::Pause);
+                self.paused.store(paused, Ordering::SeqCst);
+                info!("Sync {}", if paused { "paused" } else { "resumed" });
+                Response::done()
+            }
+            Request::Shutdown => {
+                self.shutdown.notify_one();
+                Response::done()
+            }
+        }
+    }
+
+    // 本机选区变化：按共用的同步规则记录历史并发送。
+    // 没有界面可以确认，需要确认的敏感内容按拦截处理
+    fn handle_local_change(&self, runtime: &tokio::runtime::Handle, selection: Selection, content: ClipboardContent) {
+        let paused = self.paused.load(Ordering::SeqCst);
+        match sync::local_change(&self.settings(), &self.history, selection, content, paused) {
+            Outgoing::Skip => {}
+            Outgoing::Blocked(kind) | Outgoing::Confirm { kind, .. } => {
+                info!("Not syncing {:?}: looks like a {}", selection, kind.label());
+            }
+            Outgoing::Send { text, options } => {
+                runtime.spawn(async move {
+                    if let Err(e) = ble::send_clipboard_update(&text, options).await {
+                        error!("Failed to sync clipboard update: {}", e);
+                    }
+                });
+            }
+        }
+    }
+
+    // 通过控制 socket 发送的内容
+    async fn send_text(self: &Arc<Self>, text: String, address: Option<String>) -> Result<(), String> {
+        if let Some(address) = address {
+            if ble::connected_address().as_deref() != Some(address.as_str()) {
+                self.connect(address).await?;
+            }
+        }
+        let (text, options) = sync::prepare_send(&self.settings(), &text)?;
+        ble::send_clipboard_update(&text, options).await.map_err(String::from)
+    }
+
+    // 连接设备并在后台接收对方的剪贴板更新，直到连接断开
+    async fn connect(self: &Arc<Self>, address: String) -> Result<(), String> {
+        self.connect_device(&address).await?;
+        let daemon = self.clone();
+        tokio::spawn(async move { daemon.listen().await });
+        Ok(())
+    }
+
+    async fn connect_device(&self, address: &str) -> Result<(), String> {
+        let settings = self.settings();
+        if !settings.transports.ble {
+            return Err("BLE transport is disabled in settings".to_string());
+        }
+        ble::connect_to_clipboard_device(
+            address,
+            Duration::from_secs(settings.scan.connect_scan_secs),
+            settings.adapter.as_deref(),
+        )
+        .await?;
+        if let Err(e) = self.settings.update(|settings| settings.trust_device(address, None)) {
+            error!("Failed to remember device {}: {}", address, e);
+        }
+        Ok(())
+    }
+
+    fn trust(&self, address: &str, name: Option<String>) -> Result<(), String> {
+        self.settings.update(|settings| settings.trust_device(address, name))?;
+        Ok(())
+    }
+
+    // 等待当前连接的设备发来下一条内容
+    async fn receive(self: &Arc<Self>, address: Option<String>) -> Result<Received, String> {
+        let mut received = self.received.subscribe();
+        match address {
+            Some(address) if ble::connected_address().as_deref() != Some(address.as_str()) => {
+                self.connect(address).await?;
+            }
+            None if !ble::is_connected() => return Err("pastod is not connected to a device".to_string()),
+            _ => {}
+        }
+        received
+            .recv()
+            .await
+            .map_err(|e| format!("Failed to receive clipboard update: {}", e))
+    }
+
+    async fn listen(self: Arc<Self>) {
+        let daemon = self.clone();
+        let result = ble::listen_for_clipboard_updates(move |received| {
+            daemon.forward_received(&received);
+            daemon.apply_received(received);
+        })
+        .await;
+        if let Err(e) = result {
+            error!("Failed to listen for clipboard updates: {}", e);
+        }
+        info!("Disconnected");
+    }
+
+    // 保持与配置中设备的连接，连接失败或断开后等待 reconnect_secs 再试
+    async fn keep_connected(self: Arc<Self>, address: String) {
+        loop {
+            if !ble::is_connected() {
+                match self.connect_device(&address).await {
+                    Ok(()) => {
+                        self.clone().listen().await;
+                        // 监听失败时连接状态可能还在，清除后才会重连
+                        ble::disconnect(&address).await;
+                    }
+                    Err(e) => error!("Failed to connect to {}: {}", address, e),
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(self.config.reconnect_secs)).await;
+        }
+    }
+
+    // 有客户端在等待时转发，不受接收设置和暂停影响，与不经过 pastod 的 pasto recv 一致
+    fn forward_received(&self, received: &ble::ReceivedClipboard) {
+        if self.received.receiver_count() == 0 {
+            return;
+        }
+        let _ = self.received.send(Received {
+            device: received.device.clone(),
+            content: sync::apply_transforms(&self.settings(), Direction::Incoming, &received.content),
+            kind: received.kind.clone(),
+            ttl_secs: received.ttl_secs,
+        });
+    }
+
+    // 按最后写入者胜出写入收到的内容，并记录到历史；带有效期的内容到期后自动清除
+    fn apply_received(&self, received: ble::ReceivedClipboard) {
+        let settings = self.settings();
+        if !settings.receives() || self.paused.load(Ordering::SeqCst) {
+            info!("Ignoring clipboard from {}: receiving disabled or paused", received.device);
+            return;
+        }
+        let Some(applied) = sync::apply_received(&settings, &self.clipboard, &self.history, received) else {
+            return;
+        };
+        if let Some(ttl_secs) = applied.undo_ttl_secs {
+            tokio::spawn(sync::expire_undo_after(self.clipboard.clone(), ttl_secs));
+        }
+        if let Some(ttl_secs) = applied.ttl_secs {
+            tokio::spawn(sync::clear_after_ttl(self.clipboard.clone(), applied.content, ttl_secs));
+        }
+    }
+}
+
+// 向正在运行的 pastod 发送一个请求，无法连接（未运行）时返回 None
+#[cfg(unix)]
+pub fn request(socket: &Path, request: &Request) -> Option<Result<Response, String>> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(socket).ok()?;
+    let result = (|| {
+        let mut line = serde_json::to_string(request)
+            .map_err(|e| format!("Failed to serialize request: {}", e))?;
+        line.push('\n');
+        stream
+            .write_all(line.as_bytes())
+            .map_err(|e| format!("Failed to send request to pastod: {}", e))?;
+        let mut reply = String::new();
+        BufReader::new(&stream)
+            .read_line(&mut reply)
+            .map_err(|e| format!("Failed to read response from pastod: {}", e))?;
+        serde_json::from_str(&reply).map_err(|e| format!("Invalid response from pastod: {}", e))
+    })();
+    Some(result)
+}
+
+#[cfg(not(unix))]
+pub fn request(_socket: &Path, _request: &Request) -> Option<Result<Response, String>> {
+    None
+}
+
+#[cfg(unix)]
+async fn serve_control(daemon: Arc<Daemon>) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    use tokio::net::UnixListener;
+
+    let path = daemon.config.socket.clone();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create socket directory: {}", e))?;
+    }
+    // 上次异常退出时留下的 socket 文件
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)
+        .map_err(|e| format!("Failed to bind control socket {}: {}", path.display(), e))?;
+    // 只允许当前用户控制
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+        .map_err(|e| format!("Failed to set control socket permissions: {}", e))?;
+    info!("Listening on {}", path.display());
+
+    loop {
+        let (stream, _) = listener
+            .accept()
+            .await
+            .map_err(|e| format!("Failed to accept control connection: {}", e))?;
+        let daemon = daemon.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_control_connection(daemon, stream).await {
+                error!("Control connection failed: {}", e);
+            }
+        });
+    }
+}
+
+// 没有 Unix socket 的平台上只运行同步，不提供控制接口
+#[cfg(not(unix))]
+async fn serve_control(_daemon: Arc<Daemon>) -> Result<(), String> {
+    error!("Control socket is not supported on this platform");
+    std::future::pending().await
+}
+
+#[cfg(unix)]
+async fn handle_control_connection(daemon: Arc<Daemon>, stream: tokio::net::UnixStream) -> Result<(), String> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .map_err(|e| format!("Failed to read request: {}", e))?
+    {
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => daemon.handle_request(request).await,
+            Err(e) => Response::error(format!("Invalid request: {}", e)),
+        };
+        let mut reply = serde_json::to_string(&response)
+            .map_err(|e| format!("Failed to serialize response: {}", e))?;
+        reply.push('\n');
+        writer
+            .write_all(reply.as_bytes())
+            .await
+            .map_err(|e| format!("Failed to write response: {}", e))?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn terminate_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+    match signal(SignalKind::terminate()) {
+        Ok(mut terminate) => {
+            terminate.recv().await;
+        }
+        Err(e) => {
+            error!("Failed to listen for SIGTERM: {}", e);
+            std::future::pending::<()>().await
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn terminate_signal() {
+    std::future::pending::<()>().await
+}
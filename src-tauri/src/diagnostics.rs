@@ -0,0 +1,240 @@
+use crate::ble;
+use crate::error::PastoError;
+use crate::history;
+use crate::logging;
+use crate::settings::{Settings, REDACTED};
+use serde::Serialize;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+// 诊断包中包含的最近日志文件数
+const MAX_EXPORTED_LOG_FILES: usize = 3;
+
+// 导出诊断包所需的、由调用方提供的信息
+pub struct DiagnosticsSource {
+    pub app_version: String,
+    pub settings: Settings,
+    pub log_dir: Option<PathBuf>,
+}
+
+#[derive(Serialize)]
+struct SystemInfo {
+    app_version: String,
+    os: String,
+    os_version: String,
+    arch: &'static str,
+    generated_at_ms: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum AdapterList {
+    Adapters(Vec<ble::AdapterReport>),
+    Error { code: &'static str, error: String },
+}
+
+#[derive(Serialize)]
+struct ScanInfo {
+    last_scan: Option<ble::ScanReport>,
+    last_service_discovery: Option<ble::ServiceDiscovery>,
+}
+
+// 把日志、脱敏后的设置、适配器列表、最近的扫描结果和连接记录打包成 zip，
+// 用户提交问题时附上即可，不需要手动复制终端输出
+pub async fn export(path: &Path, source: DiagnosticsSource) -> Result<(), PastoError> {
+    // 适配器不可用本身就是要排查的问题，记录错误而不是中断导出
+    let adapters = match ble::list_adapters().await {
+        Ok(adapters) => AdapterList::Adapters(adapters),
+        Err(e) => AdapterList::Error {
+            code: e.code(),
+            error: e.chain(),
+        },
+    };
+    let os = os_info::get();
+    let system = SystemInfo {
+        app_version: source.app_version,
+        os: os.os_type().to_string(),
+        os_version: os.version().to_string(),
+        arch: std::env::consts::ARCH,
+        generated_at_ms: history::now_millis(),
+    };
+    let mut scan = ScanInfo {
+        last_scan: ble::last_scan(),
+        last_service_discovery: ble::last_service_discovery(),
+    };
+    let mut connections = ble::connection_history();
+    // 日志中出现的地址按设置、扫描和连接记录中已知的地址替换
+    let addresses = known_addresses(&source.settings, &scan, &connections);
+    redact_scan(&mut scan);
+    redact_connections(&mut connections);
+
+    let file = File::create(path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    write_json(&mut zip, "system.json", &system)?;
+    write_json(&mut zip, "settings.json", &source.settings.redacted())?;
+    write_json(&mut zip, "adapters.json", &adapters)?;
+    write_json(&mut zip, "scan.json", &scan)?;
+    write_json(&mut zip, "connections.json", &connections)?;
+
+    if let Some(log_dir) = &source.log_dir {
+        let files = logging::log_files(log_dir);
+        for log_file in files.iter().rev().take(MAX_EXPORTED_LOG_FILES) {
+            let Some(name) = log_file.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let contents = std::fs::read(log_file)
+                .map_err(|e| format!("Failed to read {}: {}", log_file.display(), e))?;
+            // 开启过 debug_contents 时日志中可能有剪贴板内容，不随诊断包导出
+            let contents = redact_log(&logging::strip_contents(&contents), &addresses);
+            write_entry(&mut zip, &format!("logs/{}", name), contents.as_bytes())?;
+        }
+    }
+
+    zip.finish()
+        .map_err(|e| format!("Failed to write diagnostics archive: {}", e))?;
+    Ok(())
+}
+
+// 与 Settings::redacted 一致：地址换成哈希，名称换成占位文本
+fn redact_scan(scan: &mut ScanInfo) {
+    if let Some(report) = &mut scan.last_scan {
+        for peripheral in &mut report.peripherals {
+            peripheral.address = Settings::redact_address(&peripheral.address);
+            if peripheral.name.is_some() {
+                peripheral.name = Some(REDACTED.to_string());
+            }
+        }
+    }
+    if let Some(discovery) = &mut scan.last_service_discovery {
+        discovery.address = Settings::redact_address(&discovery.address);
+    }
+}
+
+// 失败原因中也可能带有地址
+fn redact_connections(events: &mut [ble::ConnectionEvent]) {
+    for event in events {
+        let redacted = Settings::redact_address(&event.address);
+        if let ble::ConnectionEventKind::Failed { error, .. } = &mut event.kind {
+            *error = replace_ignore_case(error, &event.address, &redacted);
+        }
+        event.address = redacted;
+    }
+}
+
+fn known_addresses(settings: &Settings, scan: &ScanInfo, connections: &[ble::ConnectionEvent]) -> Vec<String> {
+    let mut addresses: Vec<String> = settings
+        .trusted_devices
+        .iter()
+        .map(|device| device.address.clone())
+        .chain(settings.rules.primary.devices.keys().cloned())
+        .chain(settings.rules.transforms.devices.keys().cloned())
+        .chain(scan.last_scan.iter().flat_map(|report| {
+            report.peripherals.iter().map(|peripheral| peripheral.address.clone())
+        }))
+        .chain(scan.last_service_discovery.iter().map(|discovery| discovery.address.clone()))
+        .chain(connections.iter().map(|event| event.address.clone()))
+        .filter(|address| !address.is_empty())
+        .map(|address| address.to_ascii_uppercase())
+        .collect();
+    addresses.sort();
+    addresses.dedup();
+    addresses
+}
+
+fn redact_log(log: &[u8], addresses: &[String]) -> String {
+    addresses
+        .iter()
+        .fold(String::from_utf8_lossy(log).into_owned(), |log, address| {
+            replace_ignore_case(&log, address, &Settings::redact_address(address))
+        })
+}
+
+// 地址只含 ASCII，按 ASCII 忽略大小写查找；ASCII 转小写不改变字节位置
+fn replace_ignore_case(text: &str, from: &str, to: &str) -> String {
+    if from.is_empty() {
+        return text.to_string();
+    }
+    let lower = text.to_ascii_lowercase();
+    let mut result = String::with_capacity(text.len());
+    let mut end = 0;
+    for (index, _) in lower.match_indices(&from.to_ascii_lowercase()) {
+        result.push_str(&text[end..index]);
+        result.push_str(to);
+        end = index + from.len();
+    }
+    result.push_str(&text[end..]);
+    result
+}
+
+fn write_json<T: Serialize>(zip: &mut ZipWriter<File>, name: &str, value: &T) -> Result<(), PastoError> {
+    let contents = serde_json::to_vec_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", name, e))?;
+    write_entry(zip, name, &contents)
+}
+
+fn write_entry(zip: &mut ZipWriter<File>, name: &str, contents: &[u8]) -> Result<(), PastoError> {
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file(name, options)
+        .and_then(|()| zip.write_all(contents).map_err(Into::into))
+        .map_err(|e| format!("Failed to write {} to diagnostics archive: {}", name, e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "AA:BB:CC:DD:EE:FF";
+
+    #[test]
+    fn redacts_addresses_and_names_in_scan_and_connections() {
+        let mut scan = ScanInfo {
+            last_scan: Some(ble::ScanReport {
+                timestamp_ms: 0,
+                peripherals: vec![ble::ScannedPeripheral {
+                    address: ADDRESS.to_string(),
+                    name: Some("Phone".to_string()),
+                    rssi: None,
+                    services: Vec::new(),
+                    has_clipboard_service: true,
+                }],
+            }),
+            last_service_discovery: None,
+        };
+        let mut connections = vec![ble::ConnectionEvent {
+            timestamp_ms: 0,
+            address: ADDRESS.to_string(),
+            kind: ble::ConnectionEventKind::Failed {
+                code: "device_not_found",
+                error: "Device aa:bb:cc:dd:ee:ff not found".to_string(),
+            },
+        }];
+        redact_scan(&mut scan);
+        redact_connections(&mut connections);
+
+        let hashed = Settings::redact_address(ADDRESS);
+        let json = serde_json::to_string(&(&scan, &connections)).unwrap();
+        assert!(!json.to_ascii_uppercase().contains(ADDRESS));
+        assert!(!json.contains("Phone"));
+        assert_eq!(scan.last_scan.unwrap().peripherals[0].address, hashed);
+        assert_eq!(connections[0].address, hashed);
+        assert!(json.contains(&format!("Device {} not found", hashed)));
+    }
+
+    #[test]
+    fn redacts_known_addresses_in_logs() {
+        let addresses = [ADDRESS.to_string()];
+        let log = "INFO Connected to aa:bb:cc:dd:ee:ff\nINFO Connected to 11:22:33:44:55:66\n";
+        assert_eq!(
+            redact_log(log.as_bytes(), &addresses),
+            format!(
+                "INFO Connected to {}\nINFO Connected to 11:22:33:44:55:66\n",
+                Settings::redact_address(ADDRESS)
+            )
+        );
+    }
+}
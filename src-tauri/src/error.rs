@@ -0,0 +1,247 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::error::Error as _;
+
+// 返回给前端的错误。序列化为 {code, message, details}，前端按 code 区分处理，
+// code 一经发布不再修改；完整的错误链通过 chain() 写入日志
+#[derive(Debug, thiserror::Error)]
+pub enum PastoError {
+    #[error("No Bluetooth adapters found")]
+    AdapterMissing,
+    #[error("Bluetooth adapter {0} not found")]
+    AdapterNotFound(String),
+    #[error("Bluetooth is turned off")]
+    BluetoothOff,
+    #[error("No device connected")]
+    NotConnected,
+    #[error("Device with address {0} not found")]
+    DeviceNotFound(String),
+    #[error("Clipboard service not found")]
+    ServiceNotFound { expected: String, available: Vec<String> },
+    #[error("Clipboard characteristic not found")]
+    CharacteristicNotFound { expected: String, available: Vec<String> },
+    #[error("{context}")]
+    Bluetooth {
+        context: String,
+        #[source]
+        source: btleplug::Error,
+    },
+    #[error("Clipboard does not contain text")]
+    ClipboardEmpty,
+    #[error("{context}")]
+    Clipboard {
+        context: String,
+        #[source]
+        source: arboard::Error,
+    },
+    // 设置等输入未通过校验，fields 列出每个出错的字段
+    #[error("{message}")]
+    Validation {
+        message: String,
+        fields: Vec<FieldError>,
+    },
+    // 读写设置文件或历史数据库失败
+    #[error("{context}")]
+    Storage {
+        context: String,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    #[error("{0}")]
+    Other(String),
+}
+
+// 未通过校验的字段，field 为设置中的路径，如 "scan.scan_secs"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.field, self.message)
+    }
+}
+
+impl PastoError {
+    // 与具体字段无关的校验错误，如设置文件格式不对
+    pub fn validation(message: impl Into<String>) -> Self {
+        PastoError::Validation {
+            message: message.into(),
+            fields: Vec::new(),
+        }
+    }
+
+    // 按字段列出的校验错误，message 汇总全部字段
+    pub fn invalid_fields(context: &str, fields: Vec<FieldError>) -> Self {
+        let summary: Vec<String> = fields.iter().map(FieldError::to_string).collect();
+        PastoError::Validation {
+            message: format!("{}: {}", context, summary.join("; ")),
+            fields,
+        }
+    }
+
+    pub fn bluetooth(context: impl Into<String>, source: btleplug::Error) -> Self {
+        PastoError::Bluetooth {
+            context: context.into(),
+            source,
+        }
+    }
+
+    pub fn clipboard(context: impl Into<String>, source: arboard::Error) -> Self {
+        PastoError::Clipboard {
+            context: context.into(),
+            source,
+        }
+    }
+
+    pub fn storage(
+        context: impl Into<String>,
+        source: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Self {
+        PastoError::Storage {
+            context: context.into(),
+            source: source.into(),
+        }
+    }
+
+    // 稳定的错误代码，底层错误按类型归类
+    pub fn code(&self) -> &'static str {
+        match self {
+            PastoError::AdapterMissing | PastoError::AdapterNotFound(_) => "adapter_missing",
+            PastoError::BluetoothOff => "bluetooth_off",
+            PastoError::NotConnected => "not_connected",
+            PastoError::DeviceNotFound(_) => "device_not_found",
+            PastoError::ServiceNotFound { .. } => "service_not_found",
+            PastoError::CharacteristicNotFound { .. } => "characteristic_not_found",
+            PastoError::Bluetooth { source, .. } => match source {
+                btleplug::Error::PermissionDenied => "permission_denied",
+                btleplug::Error::DeviceNotFound => "device_not_found",
+                btleplug::Error::NotConnected => "not_connected",
+                btleplug::Error::NoSuchCharacteristic => "characteristic_not_found",
+                btleplug::Error::NotSupported(_) => "not_supported",
+                btleplug::Error::TimedOut(_) => "timed_out",
+                _ => "bluetooth",
+            },
+            PastoError::ClipboardEmpty => "clipboard_empty",
+            PastoError::Clipboard { source, .. } => match source {
+                arboard::Error::ClipboardOccupied => "clipboard_busy",
+                arboard::Error::ClipboardNotSupported => "clipboard_unsupported",
+                arboard::Error::ContentNotAvailable => "clipboard_empty",
+                _ => "clipboard",
+            },
+            PastoError::Validation { .. } => "validation",
+            PastoError::Storage { .. } => "storage",
+            PastoError::Other(_) => "other",
+        }
+    }
+
+    // 给用户排查问题用的补充信息
+    pub fn details(&self) -> Option<String> {
+        match self {
+            PastoError::ServiceNotFound {
+                expected,
+                available,
+            }
+            | PastoError::CharacteristicNotFound {
+                expected,
+                available,
+            } => Some(format!("Expected UUID: {}, available: [{}]", expected, available.join(", "))),
+            _ => self.source().map(|source| source.to_string()),
+        }
+    }
+
+    // 带上全部底层原因的完整描述，用于日志
+    pub fn chain(&self) -> String {
+        let mut text = self.to_string();
+        let mut source = self.source();
+        while let Some(error) = source {
+            text.push_str(": ");
+            text.push_str(&error.to_string());
+            source = error.source();
+        }
+        if let Some(details) = self.details().filter(|_| self.source().is_none()) {
+            text.push_str(&format!(" ({})", details));
+        }
+        text
+    }
+}
+
+impl Serialize for PastoError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("PastoError", 4)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("details", &self.details())?;
+        // 只有校验错误带字段列表，前端据此标出对应的输入项
+        match self {
+            PastoError::Validation { fields, .. } => state.serialize_field("fields", fields)?,
+            _ => state.skip_field("fields")?,
+        }
+        state.end()
+    }
+}
+
+// 尚未归类的错误
+impl From<String> for PastoError {
+    fn from(message: String) -> Self {
+        PastoError::Other(message)
+    }
+}
+
+impl From<&str> for PastoError {
+    fn from(message: &str) -> Self {
+        PastoError::Other(message.to_string())
+    }
+}
+
+// 命令行和守护进程只需要文本
+impl From<PastoError> for String {
+    fn from(error: PastoError) -> Self {
+        error.chain()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validation_errors_serialize_fields() {
+        let error = PastoError::invalid_fields(
+            "Invalid settings",
+            vec![
+                FieldError::new("device_name", "must not be empty"),
+                FieldError::new("scan.scan_secs", "must be between 1 and 60, got 0"),
+            ],
+        );
+
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["code"], "validation");
+        assert_eq!(
+            json["message"],
+            "Invalid settings: device_name must not be empty; scan.scan_secs must be between 1 and 60, got 0"
+        );
+        assert!(json["details"].is_null());
+        assert_eq!(json["fields"][0]["field"], "device_name");
+        assert_eq!(json["fields"][0]["message"], "must not be empty");
+        assert_eq!(json["fields"][1]["field"], "scan.scan_secs");
+    }
+
+    #[test]
+    fn other_errors_have_no_fields() {
+        let json = serde_json::to_value(PastoError::NotConnected).unwrap();
+        assert_eq!(json["code"], "not_connected");
+        assert!(json.get("fields").is_none());
+    }
+}
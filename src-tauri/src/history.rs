@@ -0,0 +1,763 @@
+use crate::content_kind::{ContentClass, ContentKind};
+use crate::error::PastoError;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tracing::{debug, error};
+
+// 文本内容的类型标记
+pub const CONTENT_TYPE_TEXT: &str = "text/plain";
+
+// 数据库结构版本，保存在 PRAGMA user_version 中
+const SCHEMA_VERSION: i64 = 3;
+
+const SELECT_ENTRY: &str = "SELECT id, content, content_type, size, source_device, timestamp, pinned, \
+     kind, language FROM history";
+
+// 一条剪贴板历史记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    pub id: i64,
+    pub content: String,
+    pub content_type: String,
+    pub size: u64,
+    // 来源设备，本机复制的内容为 None
+    pub source_device: Option<String>,
+    // 毫秒时间戳
+    pub timestamp: u64,
+    // 置顶的记录不受保留策略清理
+    pub pinned: bool,
+    // 检测到的内容类型，脱敏记录和旧记录为 None
+    pub kind: Option<ContentKind>,
+    pub language: Option<String>,
+}
+
+// 历史保留策略，各项为 None 表示不限制。
+// 数量和总大小只统计未置顶的记录，超出时从最旧的开始清理。
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    pub max_items: Option<u64>,
+    pub max_age_secs: Option<u64>,
+    pub max_total_bytes: Option<u64>,
+    // 超过此大小的内容不会被记录
+    pub max_item_bytes: Option<u64>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_items: Some(1000),
+            max_age_secs: Some(30 * 24 * 60 * 60),
+            max_total_bytes: Some(64 * 1024 * 1024),
+            max_item_bytes: Some(1024 * 1024),
+        }
+    }
+}
+
+// 待写入的新记录
+#[derive(Debug, Clone)]
+pub struct NewHistoryEntry {
+    pub content: String,
+    pub content_type: String,
+    pub source_device: Option<String>,
+    pub timestamp: u64,
+    pub class: Option<ContentClass>,
+}
+
+impl NewHistoryEntry {
+    pub fn text(content: String, source_device: Option<String>) -> Self {
+        Self {
+            content,
+            content_type: CONTENT_TYPE_TEXT.to_string(),
+            source_device,
+            timestamp: now_millis(),
+            class: None,
+        }
+    }
+
+    pub fn with_class(mut self, class: Option<ContentClass>) -> Self {
+        self.class = class;
+        self
+    }
+}
+
+// 分页结果
+#[derive(Debug, Serialize, Clone)]
+pub struct HistoryPage {
+    pub entries: Vec<HistoryEntry>,
+    pub total: u64,
+}
+
+// 基于 SQLite 的本地剪贴板历史
+pub struct HistoryStore {
+    conn: Mutex<Connection>,
+    policy: Mutex<RetentionPolicy>,
+}
+
+impl HistoryStore {
+    pub fn open(path: &Path) -> Result<Self, PastoError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| PastoError::storage("Failed to create history directory", e))?;
+        }
+        let conn = Connection::open(path)
+            .map_err(|e| PastoError::storage("Failed to open history database", e))?;
+        Self::init(conn)
+    }
+
+    // 仅存在于内存中的历史，用于无法访问数据目录的情况
+    pub fn open_in_memory() -> Result<Self, PastoError> {
+        let conn = Connection::open_in_memory()
+            .map_err(|e| PastoError::storage("Failed to open in-memory history database", e))?;
+        Self::init(conn)
+    }
+
+    fn init(mut conn: Connection) -> Result<Self, PastoError> {
+        migrate(&mut conn)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            policy: Mutex::new(RetentionPolicy::default()),
+        })
+    }
+
+    pub fn policy(&self) -> Result<RetentionPolicy, PastoError> {
+        self.policy
+            .lock()
+            .map(|policy| policy.clone())
+            .map_err(|e| PastoError::Other(format!("Failed to lock retention policy: {}", e)))
+    }
+
+    // 更新保留策略并立即按新策略清理
+    pub fn set_policy(&self, policy: RetentionPolicy) -> Result<u64, PastoError> {
+        match self.policy.lock() {
+            Ok(mut current) => *current = policy,
+            Err(e) => return Err(format!("Failed to lock retention policy: {}", e).into()),
+        }
+        self.compact()
+    }
+
+    // 写入一条记录并按保留策略清理，超过单条大小上限的内容不记录（返回 None）
+    pub fn record(&self, entry: NewHistoryEntry) -> Result<Option<HistoryEntry>, PastoError> {
+        let policy = self.policy()?;
+        let size = entry.content.len() as u64;
+        if policy.max_item_bytes.is_some_and(|max| size > max) {
+            debug!(
+                "Skipping {} byte item, exceeds per-item cap",
+                size
+            );
+            return Ok(None);
+        }
+
+        let mut conn = self.lock()?;
+        let tx = conn
+            .transaction()
+            .map_err(|e| PastoError::storage("Failed to start history transaction", e))?;
+        tx.execute(
+            "INSERT INTO history (content, content_type, size, source_device, timestamp, kind, language)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                entry.content,
+                entry.content_type,
+                size as i64,
+                entry.source_device,
+                entry.timestamp as i64,
+                entry.class.as_ref().map(|class| class.kind.as_str()),
+                entry.class.as_ref().and_then(|class| class.language.as_deref())
+            ],
+        )
+        .map_err(|e| PastoError::storage("Failed to record history entry", e))?;
+        let id = tx.last_insert_rowid();
+        enforce_policy(&tx, &policy, now_millis())?;
+        tx.commit()
+            .map_err(|e| PastoError::storage("Failed to commit history entry", e))?;
+
+        let (kind, language) = match entry.class {
+            Some(class) => (Some(class.kind), class.language),
+            None => (None, None),
+        };
+        Ok(Some(HistoryEntry {
+            id,
+            content: entry.content,
+            content_type: entry.content_type,
+            size,
+            source_device: entry.source_device,
+            timestamp: entry.timestamp,
+            pinned: false,
+            kind,
+            language,
+        }))
+    }
+
+    // 按当前保留策略清理，返回删除的条数
+    pub fn compact(&self) -> Result<u64, PastoError> {
+        let policy = self.policy()?;
+        let mut conn = self.lock()?;
+        let tx = conn
+            .transaction()
+            .map_err(|e| PastoError::storage("Failed to start history transaction", e))?;
+        let evicted = enforce_policy(&tx, &policy, now_millis())?;
+        tx.commit()
+            .map_err(|e| PastoError::storage("Failed to commit history compaction", e))?;
+        Ok(evicted)
+    }
+
+    pub fn set_pinned(&self, id: i64, pinned: bool) -> Result<bool, PastoError> {
+        let conn = self.lock()?;
+        let updated = conn
+            .execute(
+                "UPDATE history SET pinned = ?2 WHERE id = ?1",
+                params![id, pinned],
+            )
+            .map_err(|e| PastoError::storage(format!("Failed to update history entry {}", id), e))?;
+        Ok(updated > 0)
+    }
+
+    // 按时间倒序分页
+    pub fn list(&self, offset: u64, limit: u64) -> Result<HistoryPage, PastoError> {
+        let conn = self.lock()?;
+        let total: i64 = conn
+            .query_row("SELECT COUNT(*) FROM history", [], |row| row.get(0))
+            .map_err(|e| PastoError::storage("Failed to count history entries", e))?;
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "{} ORDER BY timestamp DESC, id DESC LIMIT ?1 OFFSET ?2",
+                SELECT_ENTRY
+            ))
+            .map_err(|e| PastoError::storage("Failed to query history", e))?;
+        let entries = stmt
+            .query_map(params![limit as i64, offset as i64], entry_from_row)
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| PastoError::storage("Failed to read history entries", e))?;
+
+        Ok(HistoryPage {
+            entries,
+            total: total as u64,
+        })
+    }
+
+    // 子串搜索（不区分大小写），按时间倒序。
+    // SQLite 的 lower() 只处理 ASCII，这里在 Rust 中按 Unicode 折叠大小写
+    pub fn search(&self, query: &str, limit: u64) -> Result<Vec<HistoryEntry>, PastoError> {
+        let query = query.to_lowercase();
+        let conn = self.lock()?;
+        let mut stmt = conn
+            .prepare(&format!("{} ORDER BY timestamp DESC, id DESC", SELECT_ENTRY))
+            .map_err(|e| PastoError::storage("Failed to search history", e))?;
+        let entries = stmt
+            .query_map([], entry_from_row)
+            .and_then(|rows| {
+                rows.filter(|row| {
+                    row.as_ref()
+                        .map_or(true, |entry| entry.content.to_lowercase().contains(&query))
+                })
+                .take(limit as usize)
+                .collect::<Result<Vec<_>, _>>()
+            })
+            .map_err(|e| PastoError::storage("Failed to read history entries", e))?;
+        Ok(entries)
+    }
+
+    // 最近从其他设备收到的记录，按时间倒序
+    pub fn recent_received(&self, limit: u64) -> Result<Vec<HistoryEntry>, PastoError> {
+        let conn = self.lock()?;
+        let mut stmt = conn
+            .prepare(&format!(
+                "{} WHERE source_device IS NOT NULL ORDER BY timestamp DESC, id DESC LIMIT ?1",
+                SELECT_ENTRY
+            ))
+            .map_err(|e| PastoError::storage("Failed to query history", e))?;
+        let entries = stmt
+            .query_map(params![limit as i64], entry_from_row)
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| PastoError::storage("Failed to read history entries", e))?;
+        Ok(entries)
+    }
+
+    // 模糊搜索：查询字符按顺序出现即命中，按匹配得分排序
+    pub fn fuzzy_search(&self, query: &str, limit: u64) -> Result<Vec<HistoryEntry>, PastoError> {
+        let conn = self.lock()?;
+        let mut stmt = conn
+            .prepare(&format!("{} ORDER BY timestamp DESC, id DESC", SELECT_ENTRY))
+            .map_err(|e| PastoError::storage("Failed to search history", e))?;
+        let entries = stmt
+            .query_map([], entry_from_row)
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| PastoError::storage("Failed to read history entries", e))?;
+
+        let mut scored: Vec<(i64, HistoryEntry)> = entries
+            .into_iter()
+            .filter_map(|entry| fuzzy_score(query, &entry.content).map(|score| (score, entry)))
+            .collect();
+        // 稳定排序，同分时保持时间倒序
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+        Ok(scored
+            .into_iter()
+            .take(limit as usize)
+            .map(|(_, entry)| entry)
+            .collect())
+    }
+
+    pub fn get(&self, id: i64) -> Result<Option<HistoryEntry>, PastoError> {
+        let conn = self.lock()?;
+        conn.query_row(
+            &format!("{} WHERE id = ?1", SELECT_ENTRY),
+            params![id],
+            entry_from_row,
+        )
+        .optional()
+        .map_err(|e| PastoError::storage(format!("Failed to read history entry {}", id), e))
+    }
+
+    pub fn delete(&self, id: i64) -> Result<bool, PastoError> {
+        let conn = self.lock()?;
+        let deleted = conn
+            .execute("DELETE FROM history WHERE id = ?1", params![id])
+            .map_err(|e| PastoError::storage(format!("Failed to delete history entry {}", id), e))?;
+        Ok(deleted > 0)
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, Connection>, PastoError> {
+        self.conn
+            .lock()
+            .map_err(|e| PastoError::Other(format!("Failed to lock history database: {}", e)))
+    }
+}
+
+fn entry_from_row(row: &Row<'_>) -> rusqlite::Result<HistoryEntry> {
+    Ok(HistoryEntry {
+        id: row.get(0)?,
+        content: row.get(1)?,
+        content_type: row.get(2)?,
+        size: row.get::<_, i64>(3)? as u64,
+        source_device: row.get(4)?,
+        timestamp: row.get::<_, i64>(5)? as u64,
+        pinned: row.get(6)?,
+        kind: row
+            .get::<_, Option<String>>(7)?
+            .and_then(|kind| ContentKind::parse(&kind)),
+        language: row.get(8)?,
+    })
+}
+
+// 按 user_version 逐级升级数据库结构。所有步骤在同一个事务中执行，
+// 中途失败时整体回滚，不会留下升级了一半的数据库
+fn migrate(conn: &mut Connection) -> Result<(), PastoError> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| PastoError::storage("Failed to start history migration", e))?;
+    let version: i64 = tx
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| PastoError::storage("Failed to read history schema version", e))?;
+
+    if version < 1 {
+        tx.execute_batch(
+            "CREATE TABLE IF NOT EXISTS history (
+                id            INTEGER PRIMARY KEY AUTOINCREMENT,
+                content       TEXT NOT NULL,
+                content_type  TEXT NOT NULL,
+                size          INTEGER NOT NULL,
+                source_device TEXT,
+                timestamp     INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS history_timestamp ON history (timestamp);",
+        )
+        .map_err(|e| PastoError::storage("Failed to initialize history database", e))?;
+    }
+    if version < 2 {
+        tx.execute_batch("ALTER TABLE history ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;")
+            .map_err(|e| PastoError::storage("Failed to add pinned column", e))?;
+    }
+    if version < 3 {
+        tx.execute_batch(
+            "ALTER TABLE history ADD COLUMN kind TEXT;
+             ALTER TABLE history ADD COLUMN language TEXT;",
+        )
+        .map_err(|e| PastoError::storage("Failed to add kind columns", e))?;
+    }
+
+    tx.pragma_update(None, "user_version", SCHEMA_VERSION)
+        .map_err(|e| PastoError::storage("Failed to update history schema version", e))?;
+    tx.commit()
+        .map_err(|e| PastoError::storage("Failed to commit history migration", e))
+}
+
+// 依次按最长保存时间、条数、总大小删除未置顶的旧记录，返回删除的条数
+fn enforce_policy(conn: &Connection, policy: &RetentionPolicy, now: u64) -> Result<u64, PastoError> {
+    let mut evicted = 0;
+
+    if let Some(max_age_secs) = policy.max_age_secs {
+        let cutoff = now.saturating_sub(max_age_secs.saturating_mul(1000));
+        evicted += conn
+            .execute(
+                "DELETE FROM history WHERE pinned = 0 AND timestamp < ?1",
+                params![cutoff as i64],
+            )
+            .map_err(|e| PastoError::storage("Failed to evict expired history entries", e))?;
+    }
+
+    if let Some(max_items) = policy.max_items {
+        evicted += conn
+            .execute(
+                "DELETE FROM history WHERE id IN (
+                    SELECT id FROM history WHERE pinned = 0
+                    ORDER BY timestamp DESC, id DESC LIMIT -1 OFFSET ?1
+                )",
+                params![max_items as i64],
+            )
+            .map_err(|e| PastoError::storage("Failed to evict excess history entries", e))?;
+    }
+
+    if let Some(max_total_bytes) = policy.max_total_bytes {
+        evicted += conn
+            .execute(
+                "DELETE FROM history WHERE id IN (
+                    SELECT id FROM (
+                        SELECT id, SUM(size) OVER (ORDER BY timestamp DESC, id DESC) AS running
+                        FROM history WHERE pinned = 0
+                    ) WHERE running > ?1
+                )",
+                params![max_total_bytes as i64],
+            )
+            .map_err(|e| PastoError::storage("Failed to evict oversized history entries", e))?;
+    }
+
+    Ok(evicted as u64)
+}
+
+// 后台定期清理，主要用于按时间过期的记录
+pub fn spawn_compaction(store: Arc<HistoryStore>, interval: Duration) {
+    thread::spawn(move || loop {
+        thread::sleep(interval);
+        match store.compact() {
+            Ok(0) => {}
+            Ok(evicted) => debug!("Compaction evicted {} entries", evicted),
+            Err(e) => error!("Compaction failed: {}", e.chain()),
+        }
+    });
+}
+
+// 子序列模糊匹配打分，不匹配返回 None。
+// 连续命中和单词开头命中加分，跳过的字符扣分。
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let mut score = 0i64;
+    let mut matched = 0usize;
+    let mut previous_match: Option<usize> = None;
+    let mut previous_char: Option<char> = None;
+
+    for (index, c) in text.chars().enumerate() {
+        if matched == query.len() {
+            break;
+        }
+
+        let lower = c.to_lowercase().next().unwrap_or(c);
+        if lower == query[matched] {
+            score += 1;
+            if previous_match.is_some_and(|p| p + 1 == index) {
+                score += 5;
+            }
+            if previous_char.is_none_or(|p| !p.is_alphanumeric()) {
+                score += 3;
+            }
+            if let Some(p) = previous_match {
+                score -= (index - p - 1).min(10) as i64;
+            }
+            previous_match = Some(index);
+            matched += 1;
+        }
+        previous_char = Some(c);
+    }
+
+    (matched == query.len()).then_some(score)
+}
+
+pub fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE_MS: u64 = 60 * 1000;
+    const NOW: u64 = 1_700_000_000_000;
+
+    fn unlimited() -> RetentionPolicy {
+        RetentionPolicy {
+            max_items: None,
+            max_age_secs: None,
+            max_total_bytes: None,
+            max_item_bytes: None,
+        }
+    }
+
+    // 不做任何清理的内存历史，按给定的 (内容, 时间戳) 写入
+    fn store_with(entries: &[(&str, u64)]) -> HistoryStore {
+        let store = HistoryStore::open_in_memory().unwrap();
+        store.set_policy(unlimited()).unwrap();
+        for (content, timestamp) in entries {
+            let mut entry = NewHistoryEntry::text(content.to_string(), None);
+            entry.timestamp = *timestamp;
+            store.record(entry).unwrap();
+        }
+        store
+    }
+
+    fn enforce(store: &HistoryStore, policy: RetentionPolicy) -> u64 {
+        let conn = store.lock().unwrap();
+        enforce_policy(&conn, &policy, NOW).unwrap()
+    }
+
+    // 剩余记录的内容，从旧到新
+    fn remaining(store: &HistoryStore) -> Vec<String> {
+        let mut entries = store.list(0, 100).unwrap().entries;
+        entries.reverse();
+        entries.into_iter().map(|entry| entry.content).collect()
+    }
+
+    fn pin(store: &HistoryStore, content: &str) {
+        let id = store.search(content, 1).unwrap()[0].id;
+        assert!(store.set_pinned(id, true).unwrap());
+    }
+
+    #[test]
+    fn evicts_entries_older_than_max_age() {
+        let store = store_with(&[
+            ("expired", NOW - 10 * MINUTE_MS),
+            ("fresh", NOW - MINUTE_MS),
+        ]);
+        let policy = RetentionPolicy {
+            max_age_secs: Some(5 * 60),
+            ..unlimited()
+        };
+        assert_eq!(enforce(&store, policy), 1);
+        assert_eq!(remaining(&store), ["fresh"]);
+    }
+
+    #[test]
+    fn evicts_oldest_entries_beyond_max_items() {
+        let store = store_with(&[("a", NOW - 3), ("b", NOW - 2), ("c", NOW - 1), ("d", NOW)]);
+        let policy = RetentionPolicy {
+            max_items: Some(2),
+            ..unlimited()
+        };
+        assert_eq!(enforce(&store, policy), 2);
+        assert_eq!(remaining(&store), ["c", "d"]);
+    }
+
+    #[test]
+    fn evicts_oldest_entries_beyond_max_total_bytes() {
+        let store = store_with(&[("aaaa", NOW - 2), ("bbbb", NOW - 1), ("cccc", NOW)]);
+        let policy = RetentionPolicy {
+            max_total_bytes: Some(10),
+            ..unlimited()
+        };
+        assert_eq!(enforce(&store, policy), 1);
+        assert_eq!(remaining(&store), ["bbbb", "cccc"]);
+    }
+
+    #[test]
+    fn applies_age_then_count_then_total_bytes() {
+        let store = store_with(&[
+            ("expired", NOW - 10 * MINUTE_MS),
+            ("one", NOW - 4),
+            ("two", NOW - 3),
+            ("three", NOW - 2),
+            ("a much longer item", NOW - 1),
+        ]);
+        // 过期的先删除，剩下 4 条中删除最旧的 "one"，最后按总大小删除 "two"
+        let policy = RetentionPolicy {
+            max_age_secs: Some(60),
+            max_items: Some(3),
+            max_total_bytes: Some(23),
+            ..unlimited()
+        };
+        assert_eq!(enforce(&store, policy), 3);
+        assert_eq!(remaining(&store), ["three", "a much longer item"]);
+    }
+
+    #[test]
+    fn count_limit_applies_to_entries_left_after_age_eviction() {
+        let store = store_with(&[
+            ("expired", NOW - 10 * MINUTE_MS),
+            ("one", NOW - 2),
+            ("two", NOW - 1),
+        ]);
+        // 按时间清理后只剩 2 条，数量限制不再删除
+        let policy = RetentionPolicy {
+            max_age_secs: Some(60),
+            max_items: Some(2),
+            ..unlimited()
+        };
+        assert_eq!(enforce(&store, policy), 1);
+        assert_eq!(remaining(&store), ["one", "two"]);
+    }
+
+    #[test]
+    fn never_evicts_pinned_entries() {
+        let store = store_with(&[
+            ("pinned expired", NOW - 10 * MINUTE_MS),
+            ("pinned large", NOW - 2),
+            ("unpinned", NOW - 1),
+        ]);
+        pin(&store, "pinned expired");
+        pin(&store, "pinned large");
+        let policy = RetentionPolicy {
+            max_age_secs: Some(0),
+            max_items: Some(0),
+            max_total_bytes: Some(0),
+            max_item_bytes: None,
+        };
+        assert_eq!(enforce(&store, policy), 1);
+        assert_eq!(remaining(&store), ["pinned expired", "pinned large"]);
+    }
+
+    #[test]
+    fn pinned_entries_do_not_count_towards_limits() {
+        let store = store_with(&[("pinned", NOW - 3), ("a", NOW - 2), ("b", NOW - 1)]);
+        pin(&store, "pinned");
+        let policy = RetentionPolicy {
+            max_items: Some(2),
+            max_total_bytes: Some(2),
+            ..unlimited()
+        };
+        assert_eq!(enforce(&store, policy), 0);
+        assert_eq!(remaining(&store), ["pinned", "a", "b"]);
+    }
+
+    #[test]
+    fn skips_items_larger_than_max_item_bytes() {
+        let store = store_with(&[]);
+        store
+            .set_policy(RetentionPolicy {
+                max_item_bytes: Some(4),
+                ..unlimited()
+            })
+            .unwrap();
+        assert!(store.record(NewHistoryEntry::text("small".into(), None)).unwrap().is_none());
+        assert!(store.record(NewHistoryEntry::text("tiny".into(), None)).unwrap().is_some());
+        assert_eq!(remaining(&store), ["tiny"]);
+    }
+
+    #[test]
+    fn migrates_version_one_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE history (
+                id            INTEGER PRIMARY KEY AUTOINCREMENT,
+                content       TEXT NOT NULL,
+                content_type  TEXT NOT NULL,
+                size          INTEGER NOT NULL,
+                source_device TEXT,
+                timestamp     INTEGER NOT NULL
+            );
+            INSERT INTO history (content, content_type, size, timestamp) VALUES ('old', 'text/plain', 3, 1);
+            PRAGMA user_version = 1;",
+        )
+        .unwrap();
+        migrate(&mut conn).unwrap();
+
+        let store = HistoryStore::init(conn).unwrap();
+        let entries = store.list(0, 10).unwrap().entries;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].content, "old");
+        assert!(!entries[0].pinned);
+        assert_eq!(entries[0].kind, None);
+    }
+
+    #[test]
+    fn failed_migration_leaves_database_unchanged() {
+        // 版本号是 1 但已有 pinned 列，升级到版本 2 时会失败
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE history (
+                id            INTEGER PRIMARY KEY AUTOINCREMENT,
+                content       TEXT NOT NULL,
+                content_type  TEXT NOT NULL,
+                size          INTEGER NOT NULL,
+                source_device TEXT,
+                timestamp     INTEGER NOT NULL,
+                pinned        INTEGER NOT NULL DEFAULT 0
+            );
+            PRAGMA user_version = 1;",
+        )
+        .unwrap();
+        let error = migrate(&mut conn).unwrap_err();
+        assert_eq!(error.code(), "storage");
+
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version, 1);
+        let has_kind = conn.prepare("SELECT kind FROM history").is_ok();
+        assert!(!has_kind);
+    }
+
+    #[test]
+    fn search_ignores_case() {
+        let store = store_with(&[("Hello World", NOW), ("ÄRGER im Büro", NOW + 1), ("unrelated", NOW + 2)]);
+        let found = |query| -> Vec<String> {
+            store.search(query, 10).unwrap().into_iter().map(|entry| entry.content).collect()
+        };
+
+        assert_eq!(found("hello"), ["Hello World"]);
+        assert_eq!(found("WORLD"), ["Hello World"]);
+        assert_eq!(found("ärger"), ["ÄRGER im Büro"]);
+        assert_eq!(found("BÜRO"), ["ÄRGER im Büro"]);
+        assert!(found("missing").is_empty());
+    }
+
+    #[test]
+    fn search_returns_newest_first_up_to_limit() {
+        let store = store_with(&[("note 1", NOW), ("note 2", NOW + 1), ("note 3", NOW + 2)]);
+        let contents: Vec<String> = store
+            .search("NOTE", 2)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.content)
+            .collect();
+        assert_eq!(contents, ["note 3", "note 2"]);
+    }
+
+    #[test]
+    fn fuzzy_matches_subsequences() {
+        assert!(fuzzy_score("gco", "git checkout").is_some());
+        assert!(fuzzy_score("GCO", "git checkout").is_some());
+        assert!(fuzzy_score("ocg", "git checkout").is_none());
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+    }
+
+    #[test]
+    fn fuzzy_scores_contiguous_and_word_start_matches_higher() {
+        let contiguous = fuzzy_score("check", "git checkout").unwrap();
+        let scattered = fuzzy_score("check", "c-h-e-c-k").unwrap();
+        assert!(contiguous > scattered);
+
+        let word_start = fuzzy_score("gc", "git checkout").unwrap();
+        let inside = fuzzy_score("gc", "logic").unwrap();
+        assert!(word_start > inside);
+    }
+
+    #[test]
+    fn fuzzy_search_orders_by_score() {
+        let store = store_with(&[("git checkout main", NOW), ("go check", NOW + 1), ("cargo", NOW + 2)]);
+        let contents: Vec<String> = store
+            .fuzzy_search("gcheck", 10)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.content)
+            .collect();
+        assert_eq!(contents, ["go check", "git checkout main"]);
+    }
+}
@@ -0,0 +1,416 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+use tracing::warn;
+
+// 混合逻辑时钟（HLC）的时间戳：毫秒级物理时间 + 同一毫秒内的计数 + 设备标识。
+// 比较时依次比较三者，任意两个时间戳都能确定先后
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[serde(from = "WireTimestamp")]
+pub struct Timestamp {
+    pub physical_ms: u64,
+    pub counter: u32,
+    pub node: String,
+}
+
+impl Ord for Timestamp {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.physical_ms
+            .cmp(&other.physical_ms)
+            .then(self.counter.cmp(&other.counter))
+            .then_with(|| self.node.cmp(&other.node))
+    }
+}
+
+impl PartialOrd for Timestamp {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// 旧版本只发送秒级时间戳，按该秒开始的毫秒、计数 0、空设备标识处理
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WireTimestamp {
+    Hybrid {
+        physical_ms: u64,
+        counter: u32,
+        node: String,
+    },
+    LegacySecs(u64),
+}
+
+impl From<WireTimestamp> for Timestamp {
+    fn from(wire: WireTimestamp) -> Self {
+        match wire {
+            WireTimestamp::Hybrid {
+                physical_ms,
+                counter,
+                node,
+            } => Timestamp {
+                physical_ms,
+                counter,
+                node,
+            },
+            WireTimestamp::LegacySecs(secs) => Timestamp {
+                physical_ms: secs.saturating_mul(1000),
+                counter: 0,
+                node: String::new(),
+            },
+        }
+    }
+}
+
+// 对方时钟比本机快这么多以上时打印警告，但仍然接受，避免丢失内容
+const MAX_EXPECTED_DRIFT_MS: u64 = 60 * 1000;
+
+// 混合逻辑时钟，同时记录当前剪贴板内容的写入时间，用于"最后写入者胜出"
+#[derive(Debug)]
+pub struct HybridClock {
+    node: String,
+    physical_ms: u64,
+    counter: u32,
+    last_write: Option<Timestamp>,
+}
+
+impl HybridClock {
+    pub const fn new(node: String) -> Self {
+        Self {
+            node,
+            physical_ms: 0,
+            counter: 0,
+            last_write: None,
+        }
+    }
+
+    // 本机事件（如本机复制）：物理时间前进则计数归零，否则在上次时间上递增计数
+    pub fn tick(&mut self, wall_ms: u64) -> Timestamp {
+        if wall_ms > self.physical_ms {
+            self.physical_ms = wall_ms;
+            self.counter = 0;
+        } else {
+            self.increment();
+        }
+        self.current()
+    }
+
+    // 收到对方的时间戳：取本机时间、上次时间、对方时间中的最大值，保证之后的本机事件都排在它后面
+    pub fn receive(&mut self, remote: &Timestamp, wall_ms: u64) -> Timestamp {
+        if remote.physical_ms > wall_ms.saturating_add(MAX_EXPECTED_DRIFT_MS) {
+            warn!(
+                "Remote clock of {} is {} ms ahead of local clock",
+                remote.node,
+                remote.physical_ms - wall_ms
+            );
+        }
+
+        let physical_ms = wall_ms.max(self.physical_ms).max(remote.physical_ms);
+        let counter = if physical_ms == self.physical_ms && physical_ms == remote.physical_ms {
+            Some(self.counter.max(remote.counter))
+        } else if physical_ms == self.physical_ms {
+            Some(self.counter)
+        } else if physical_ms == remote.physical_ms {
+            Some(remote.counter)
+        } else {
+            None
+        };
+        self.physical_ms = physical_ms;
+        match counter {
+            Some(counter) => {
+                self.counter = counter;
+                self.increment();
+            }
+            None => self.counter = 0,
+        }
+        self.current()
+    }
+
+    // 本机写入剪贴板，返回这次写入的时间戳
+    pub fn record_local_write(&mut self, wall_ms: u64) -> Timestamp {
+        let timestamp = self.tick(wall_ms);
+        self.last_write = Some(timestamp.clone());
+        timestamp
+    }
+
+    // 对方写入的内容比当前剪贴板内容更新时返回 true 并记录，否则应丢弃
+    pub fn accept_remote_write(&mut self, remote: &Timestamp, wall_ms: u64) -> bool {
+        self.receive(remote, wall_ms);
+        if self.last_write.as_ref().is_some_and(|last| remote <= last) {
+            return false;
+        }
+        self.last_write = Some(remote.clone());
+        true
+    }
+
+    // 计数加一；同一毫秒内的计数用尽时把逻辑时间推进 1 毫秒，保证时间戳仍然严格递增
+    fn increment(&mut self) {
+        match self.counter.checked_add(1) {
+            Some(counter) => self.counter = counter,
+            None => {
+                self.physical_ms = self.physical_ms.saturating_add(1);
+                self.counter = 0;
+            }
+        }
+    }
+
+    fn current(&self) -> Timestamp {
+        Timestamp {
+            physical_ms: self.physical_ms,
+            counter: self.counter,
+            node: self.node.clone(),
+        }
+    }
+}
+
+// 进程内共用的时钟。设备标识应在启动时用 set_node_id 设为设置中保存的标识，
+// 未设置时在第一次使用时随机生成
+static CLOCK: Mutex<HybridClock> = Mutex::new(HybridClock::new(String::new()));
+
+fn with_clock<T>(f: impl FnOnce(&mut HybridClock) -> T) -> T {
+    let mut clock = CLOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if clock.node.is_empty() {
+        clock.node = random_node_id();
+    }
+    f(&mut clock)
+}
+
+// 使用持久化的设备标识，这样重启后时间戳相同时仍按同一设备决定先后
+pub fn set_node_id(node: &str) {
+    if node.is_empty() {
+        return;
+    }
+    let mut clock = CLOCK.lock().unwrap_or_else(|e| e.into_inner());
+    clock.node = node.to_string();
+}
+
+// 本机事件的时间戳
+pub fn now() -> Timestamp {
+    with_clock(|clock| clock.tick(wall_clock_ms()))
+}
+
+// 本机复制了新内容
+pub fn record_local_write() -> Timestamp {
+    with_clock(|clock| clock.record_local_write(wall_clock_ms()))
+}
+
+// 收到对方的内容，返回是否应该应用
+pub fn accept_remote_write(remote: &Timestamp) -> bool {
+    with_clock(|clock| clock.accept_remote_write(remote, wall_clock_ms()))
+}
+
+// 收到不参与"最后写入者胜出"比较的时间戳（如 PRIMARY 选区），只推进时钟
+pub fn observe(remote: &Timestamp) {
+    with_clock(|clock| {
+        clock.receive(remote, wall_clock_ms());
+    })
+}
+
+fn wall_clock_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+// 随机生成的设备标识，首次启动时保存到设置中，只用于时间戳相同时决定先后
+pub fn random_node_id() -> String {
+    format!("{:016x}", RandomState::new().build_hasher().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const START_MS: u64 = 1_700_000_000_000;
+    // 模拟设备之间最多相差 10 分钟的时钟偏差
+    const MAX_SKEW_MS: i64 = 10 * 60 * 1000;
+
+    fn timestamp(physical_ms: u64, counter: u32, node: &str) -> Timestamp {
+        Timestamp {
+            physical_ms,
+            counter,
+            node: node.to_string(),
+        }
+    }
+
+    #[test]
+    fn tick_advances_past_counter_overflow() {
+        let mut clock = HybridClock::new("a".to_string());
+        clock.physical_ms = START_MS;
+        clock.counter = u32::MAX;
+        let before = clock.current();
+        let after = clock.tick(START_MS);
+        assert!(after > before);
+        assert_eq!(after, timestamp(START_MS + 1, 0, "a"));
+    }
+
+    #[test]
+    fn receive_advances_past_remote_counter_overflow() {
+        let mut clock = HybridClock::new("a".to_string());
+        let remote = timestamp(START_MS, u32::MAX, "b");
+        let after = clock.receive(&remote, START_MS - 1000);
+        assert!(after > remote);
+        assert_eq!(after, timestamp(START_MS + 1, 0, "a"));
+    }
+
+    #[test]
+    fn node_breaks_ties() {
+        let mut clock = HybridClock::new("a".to_string());
+        let local = clock.record_local_write(START_MS);
+        assert!(!clock.accept_remote_write(&timestamp(START_MS, 0, "A"), START_MS));
+        assert!(clock.accept_remote_write(&timestamp(START_MS, local.counter, "b"), START_MS));
+    }
+
+    #[test]
+    fn legacy_seconds_timestamp_is_accepted() {
+        let parsed: Timestamp = serde_json::from_str("1700000000").unwrap();
+        assert_eq!(parsed, timestamp(START_MS, 0, ""));
+    }
+
+    // 模拟中的一步：某台设备复制了新内容，或把一条待送达的内容送达某台设备
+    #[derive(Debug, Clone)]
+    enum Step {
+        Write { device: usize, elapsed_ms: u64 },
+        Deliver { device: usize, pick: usize, elapsed_ms: u64 },
+    }
+
+    fn step(devices: usize) -> impl Strategy<Value = Step> {
+        prop_oneof![
+            (0..devices, 0..50u64).prop_map(|(device, elapsed_ms)| Step::Write { device, elapsed_ms }),
+            (0..devices, any::<usize>(), 0..50u64)
+                .prop_map(|(device, pick, elapsed_ms)| Step::Deliver { device, pick, elapsed_ms }),
+        ]
+    }
+
+    struct Device {
+        clock: HybridClock,
+        skew_ms: i64,
+        // 当前剪贴板内容的时间戳和内容
+        current: Option<(Timestamp, u32)>,
+        // 已发出但还未送达本设备的内容
+        inbox: Vec<(Timestamp, u32)>,
+        // 本设备产生的最后一个时间戳，用于检查单调性
+        last_seen: Option<Timestamp>,
+    }
+
+    impl Device {
+        fn wall_ms(&self, now: u64) -> u64 {
+            now.saturating_add_signed(self.skew_ms)
+        }
+
+        fn observe_own(&mut self, timestamp: &Timestamp) -> Result<(), TestCaseError> {
+            if let Some(last) = &self.last_seen {
+                prop_assert!(timestamp > last, "{:?} is not after {:?}", timestamp, last);
+            }
+            self.last_seen = Some(timestamp.clone());
+            Ok(())
+        }
+
+        fn deliver(&mut self, now: u64, (remote, value): (Timestamp, u32)) -> Result<(), TestCaseError> {
+            let wall_ms = self.wall_ms(now);
+            if self.clock.accept_remote_write(&remote, wall_ms) {
+                self.current = Some((remote, value));
+            }
+            let after = self.clock.current();
+            self.observe_own(&after)
+        }
+    }
+
+    proptest! {
+        // 本机时钟回拨或停滞时，时间戳仍然严格递增
+        #[test]
+        fn tick_is_monotonic(walls in prop::collection::vec(0..START_MS * 2, 1..200)) {
+            let mut clock = HybridClock::new("a".to_string());
+            let mut last = None;
+            for wall_ms in walls {
+                let next = clock.tick(wall_ms);
+                if let Some(last) = &last {
+                    prop_assert!(&next > last);
+                }
+                last = Some(next);
+            }
+        }
+
+        // 收到任意时间戳后，本机之后的时间戳都排在它后面
+        #[test]
+        fn receive_orders_after_remote(
+            local_wall in 0..START_MS * 2,
+            remote_ms in 0..START_MS * 2,
+            remote_counter in any::<u32>(),
+            later_wall in 0..START_MS * 2,
+        ) {
+            let mut clock = HybridClock::new("a".to_string());
+            let before = clock.tick(local_wall);
+            let remote = timestamp(remote_ms, remote_counter, "b");
+            let received = clock.receive(&remote, local_wall);
+            prop_assert!(received > remote);
+            prop_assert!(received > before);
+            prop_assert!(clock.tick(later_wall) > received);
+        }
+
+        // 时钟有偏差的多台设备任意交错复制和送达，全部送达后都收敛到时间戳最大的那次写入
+        #[test]
+        fn last_writer_wins_converges_under_skew(
+            skews in prop::collection::vec(-MAX_SKEW_MS..=MAX_SKEW_MS, 2..5),
+            steps in prop::collection::vec(step(4), 1..100),
+        ) {
+            let mut devices: Vec<Device> = skews
+                .iter()
+                .enumerate()
+                .map(|(i, &skew_ms)| Device {
+                    clock: HybridClock::new(format!("node-{}", i)),
+                    skew_ms,
+                    current: None,
+                    inbox: Vec::new(),
+                    last_seen: None,
+                })
+                .collect();
+            let count = devices.len();
+            let mut now = START_MS;
+            let mut next_value = 0u32;
+
+            for step in steps {
+                match step {
+                    Step::Write { device, elapsed_ms } => {
+                        now += elapsed_ms;
+                        let device = device % count;
+                        let wall_ms = devices[device].wall_ms(now);
+                        let written = devices[device].clock.record_local_write(wall_ms);
+                        devices[device].observe_own(&written)?;
+                        devices[device].current = Some((written.clone(), next_value));
+                        for (i, other) in devices.iter_mut().enumerate() {
+                            if i != device {
+                                other.inbox.push((written.clone(), next_value));
+                            }
+                        }
+                        next_value += 1;
+                    }
+                    Step::Deliver { device, pick, elapsed_ms } => {
+                        now += elapsed_ms;
+                        let device = &mut devices[device % count];
+                        if !device.inbox.is_empty() {
+                            let message = device.inbox.swap_remove(pick % device.inbox.len());
+                            device.deliver(now, message)?;
+                        }
+                    }
+                }
+            }
+            for device in devices.iter_mut() {
+                while let Some(message) = device.inbox.pop() {
+                    device.deliver(now, message)?;
+                }
+            }
+
+            let newest = devices
+                .iter()
+                .filter_map(|device| device.current.clone())
+                .max_by(|a, b| a.0.cmp(&b.0));
+            for device in &devices {
+                prop_assert_eq!(&device.current, &newest);
+            }
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+// 日志文件按天滚动，文件名如 pasto.2024-01-01.log
+const LOG_FILE_PREFIX: &str = "pasto";
+const LOG_FILE_SUFFIX: &str = "log";
+// 最多保留的日志文件数
+const MAX_LOG_FILES: usize = 7;
+// 设置后忽略设置中的日志级别，格式同 RUST_LOG，如 PASTO_LOG=tauri_app_lib::ble=trace
+const LOG_ENV: &str = "PASTO_LOG";
+
+// 开启 debug_contents 时记录的剪贴板内容以此开头，导出诊断包时据此去掉这些行
+const CONTENTS_MARKER: &str = "<contents>";
+
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+static DEBUG_CONTENTS: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct LogSettings {
+    // Pasto 自身的日志级别，依赖库只记录警告和错误
+    pub level: LogLevel,
+    // 在日志中记录剪贴板内容，仅用于排查问题
+    pub debug_contents: bool,
+}
+
+// 初始化日志：输出到 stderr，指定 log_dir 时同时写入滚动日志文件。
+// 不写 stdout，命令行工具的输出（如 --json）不受影响
+pub fn init(level: LogLevel, log_dir: Option<&Path>) {
+    let (filter, handle) = reload::Layer::new(filter(level));
+    let file = log_dir.and_then(|dir| match file_appender(dir) {
+        Ok(appender) => Some(appender),
+        Err(e) => {
+            eprintln!("Failed to open log file in {}: {}", dir.display(), e);
+            None
+        }
+    });
+
+    let result = tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .with(file.map(|appender| tracing_subscriber::fmt::layer().with_ansi(false).with_writer(appender)))
+        .try_init();
+    match result {
+        Ok(()) => {
+            let _ = FILTER.set(handle);
+        }
+        Err(e) => eprintln!("Failed to initialize logging: {}", e),
+    }
+}
+
+// 应用设置中的日志选项，可在运行中修改
+pub fn apply(settings: &LogSettings) {
+    DEBUG_CONTENTS.store(settings.debug_contents, Ordering::Relaxed);
+    if let Some(handle) = FILTER.get() {
+        if let Err(e) = handle.reload(filter(settings.level)) {
+            tracing::warn!("Failed to change log level: {}", e);
+        }
+    }
+}
+
+fn filter(level: LogLevel) -> EnvFilter {
+    match std::env::var(LOG_ENV) {
+        Ok(directives) if !directives.is_empty() => EnvFilter::new(directives),
+        _ => EnvFilter::new(format!("warn,{}={}", env!("CARGO_CRATE_NAME"), level.as_str())),
+    }
+}
+
+fn file_appender(dir: &Path) -> Result<RollingFileAppender, String> {
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(dir)
+        .map_err(|e| e.to_string())
+}
+
+// 目录中的日志文件，按时间从旧到新排列
+pub fn log_files(dir: &Path) -> Vec<PathBuf> {
+    let prefix = format!("{}.", LOG_FILE_PREFIX);
+    let suffix = format!(".{}", LOG_FILE_SUFFIX);
+    let mut files: Vec<PathBuf> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(&prefix) && name.ends_with(&suffix))
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    // 文件名中的日期格式为 YYYY-MM-DD，按名称排序即按时间排序
+    files.sort();
+    files
+}
+
+// 去掉日志中记录了剪贴板内容的行，用于导出诊断包。
+// 内容按 {:?} 转义后记录，不会跨行
+pub fn strip_contents(log: &[u8]) -> Vec<u8> {
+    let marker = CONTENTS_MARKER.as_bytes();
+    log.split_inclusive(|&byte| byte == b'\n')
+        .filter(|line| !line.windows(marker.len()).any(|window| window == marker))
+        .flatten()
+        .copied()
+        .collect()
+}
+
+// 记录剪贴板内容时使用：未开启 debug_contents 时只记录长度
+pub fn contents(text: &str) -> Contents<'_> {
+    Contents(text)
+}
+
+pub struct Contents<'a>(&'a str);
+
+impl fmt::Display for Contents<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if DEBUG_CONTENTS.load(Ordering::Relaxed) {
+            write!(f, "{} {:?}", CONTENTS_MARKER, self.0)
+        } else {
+            write!(f, "<{} chars>", self.0.chars().count())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_logged_contents() {
+        DEBUG_CONTENTS.store(true, Ordering::Relaxed);
+        let log = format!(
+            "INFO connected\nDEBUG Clipboard changed: {}\nINFO sent\n",
+            contents("secret\nline")
+        );
+        DEBUG_CONTENTS.store(false, Ordering::Relaxed);
+
+        assert_eq!(strip_contents(log.as_bytes()), b"INFO connected\nINFO sent\n");
+    }
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// 本机 PRIMARY 选区（选中即复制、中键粘贴）同步到远端设备的方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PrimarySyncMode {
+    #[default]
+    Off,
+    // 写入远端的剪贴板
+    ToClipboard,
+    // 写入远端的 PRIMARY 选区
+    ToPrimary,
+}
+
+// 按设备地址分别配置，未配置的设备不同步 PRIMARY
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct PrimarySyncConfig {
+    pub devices: HashMap<String, PrimarySyncMode>,
+}
+
+impl PrimarySyncConfig {
+    // 设备地址不区分大小写，设置文件中的地址可能是手动填写的
+    pub fn mode_for(&self, address: &str) -> PrimarySyncMode {
+        self.devices
+            .iter()
+            .find(|(device, _)| device.eq_ignore_ascii_case(address))
+            .map(|(_, mode)| *mode)
+            .unwrap_or_default()
+    }
+
+    pub fn set_mode(&mut self, address: String, mode: PrimarySyncMode) {
+        self.devices.retain(|device, _| !device.eq_ignore_ascii_case(&address));
+        if mode != PrimarySyncMode::Off {
+            self.devices.insert(address, mode);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn device_addresses_ignore_case() {
+        let mut config = PrimarySyncConfig::default();
+        config.set_mode("aa:bb:cc:dd:ee:ff".to_string(), PrimarySyncMode::ToPrimary);
+        assert_eq!(config.mode_for("AA:BB:CC:DD:EE:FF"), PrimarySyncMode::ToPrimary);
+
+        config.set_mode("AA:BB:CC:DD:EE:FF".to_string(), PrimarySyncMode::ToClipboard);
+        assert_eq!(config.devices.len(), 1);
+        assert_eq!(config.mode_for("aa:bb:cc:dd:ee:ff"), PrimarySyncMode::ToClipboard);
+
+        config.set_mode("Aa:Bb:Cc:Dd:Ee:Ff".to_string(), PrimarySyncMode::Off);
+        assert!(config.devices.is_empty());
+    }
+}
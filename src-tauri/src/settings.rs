@@ -0,0 +1,672 @@
+use crate::error::{FieldError, PastoError};
+use crate::history::RetentionPolicy;
+use crate::hlc;
+use crate::logging::LogSettings;
+use crate::primary::PrimarySyncConfig;
+use crate::sensitive::SensitiveConfig;
+use crate::transform::TransformConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use xxhash_rust::xxh3::xxh3_64;
+
+// 设置文件的结构版本，结构变化时加一并在 migrate 中补充升级步骤
+pub const SETTINGS_VERSION: u32 = 1;
+
+// BLE 广播包中本机名称最多可用的字节数
+const MAX_DEVICE_NAME_BYTES: usize = 29;
+// 导出诊断信息时替换个人信息的文本
+pub const REDACTED: &str = "<redacted>";
+// 扫描时长的允许范围（秒）
+const SCAN_SECS_RANGE: std::ops::RangeInclusive<u64> = 1..=60;
+
+// 与其他设备之间的同步方向
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncDirection {
+    #[default]
+    Both,
+    SendOnly,
+    ReceiveOnly,
+}
+
+impl SyncDirection {
+    pub fn sends(&self) -> bool {
+        matches!(self, SyncDirection::Both | SyncDirection::SendOnly)
+    }
+
+    pub fn receives(&self) -> bool {
+        matches!(self, SyncDirection::Both | SyncDirection::ReceiveOnly)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct ScanTimings {
+    // 扫描设备列表的时长
+    pub scan_secs: u64,
+    // 连接前寻找目标设备的扫描时长
+    pub connect_scan_secs: u64,
+}
+
+impl Default for ScanTimings {
+    fn default() -> Self {
+        Self {
+            scan_secs: 3,
+            connect_scan_secs: 2,
+        }
+    }
+}
+
+// 启用的传输方式，目前只有 BLE
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Transports {
+    pub ble: bool,
+}
+
+impl Default for Transports {
+    fn default() -> Self {
+        Self { ble: true }
+    }
+}
+
+// 同步和记录内容时使用的规则
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct Rules {
+    pub sensitive: SensitiveConfig,
+    pub primary: PrimarySyncConfig,
+    pub transforms: TransformConfig,
+}
+
+// 全局快捷键，格式如 "CommandOrControl+Alt+V"，None 表示不注册
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct HotkeySettings {
+    // 把本机剪贴板发送给已连接设备
+    pub push: Option<String>,
+    // 读取已连接设备的剪贴板
+    pub pull: Option<String>,
+    // 暂停/恢复同步
+    pub pause: Option<String>,
+    // 撤销最近一次远端覆盖
+    pub undo: Option<String>,
+}
+
+impl Default for HotkeySettings {
+    fn default() -> Self {
+        Self {
+            push: Some("CommandOrControl+Alt+Shift+C".to_string()),
+            pull: Some("CommandOrControl+Alt+Shift+V".to_string()),
+            pause: Some("CommandOrControl+Alt+Shift+P".to_string()),
+            undo: Some("CommandOrControl+Alt+Z".to_string()),
+        }
+    }
+}
+
+impl HotkeySettings {
+    pub fn entries(&self) -> [(&'static str, Option<&str>); 4] {
+        [
+            ("push", self.push.as_deref()),
+            ("pull", self.pull.as_deref()),
+            ("pause", self.pause.as_deref()),
+            ("undo", self.undo.as_deref()),
+        ]
+    }
+}
+
+// 桌面通知
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct NotificationSettings {
+    // 收到其他设备的内容时通知
+    pub received: bool,
+    // 设备连接、断开以及首次连接新设备时通知
+    pub connection: bool,
+    // 在通知中显示内容预览，敏感内容始终不显示
+    pub show_preview: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            received: true,
+            connection: true,
+            show_preview: true,
+        }
+    }
+}
+
+// 连接过的设备，可从托盘菜单直接连接
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TrustedDevice {
+    pub address: String,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u32,
+    // 广播时使用的本机名称
+    pub device_name: String,
+    // 使用名称中包含该字符串的蓝牙适配器，None 表示使用第一个
+    pub adapter: Option<String>,
+    pub sync_direction: SyncDirection,
+    pub trusted_devices: Vec<TrustedDevice>,
+    pub scan: ScanTimings,
+    pub transports: Transports,
+    pub hotkeys: HotkeySettings,
+    pub notifications: NotificationSettings,
+    pub rules: Rules,
+    pub history: RetentionPolicy,
+    pub logging: LogSettings,
+    // 本机在混合逻辑时钟中的设备标识，首次启动时生成，用于时间戳相同时决定先后
+    pub node_id: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            device_name: "Pasto".to_string(),
+            adapter: None,
+            sync_direction: SyncDirection::default(),
+            trusted_devices: Vec::new(),
+            scan: ScanTimings::default(),
+            transports: Transports::default(),
+            hotkeys: HotkeySettings::default(),
+            notifications: NotificationSettings::default(),
+            rules: Rules::default(),
+            history: RetentionPolicy::default(),
+            logging: LogSettings::default(),
+            node_id: String::new(),
+        }
+    }
+}
+
+impl Settings {
+    // 是否把本机内容同步给其他设备
+    pub fn sends(&self) -> bool {
+        self.transports.ble && self.sync_direction.sends()
+    }
+
+    // 是否接受其他设备发来的内容
+    pub fn receives(&self) -> bool {
+        self.transports.ble && self.sync_direction.receives()
+    }
+
+    // 记住连接过的设备，已存在时更新名称
+    pub fn trust_device(&mut self, address: &str, name: Option<String>) {
+        match self.trusted_devices.iter_mut().find(|device| device.address == address) {
+            Some(device) => {
+                if name.is_some() {
+                    device.name = name;
+                }
+            }
+            None => self.trusted_devices.push(TrustedDevice {
+                address: address.to_string(),
+                name,
+            }),
+        }
+    }
+
+    // 按地址或名称（不区分大小写）查找已信任设备
+    pub fn trusted_device(&self, id: &str) -> Option<&TrustedDevice> {
+        self.trusted_devices.iter().find(|device| {
+            device.address.eq_ignore_ascii_case(id)
+                || device.name.as_deref().is_some_and(|name| name.eq_ignore_ascii_case(id))
+        })
+    }
+
+    // 导出诊断信息用：去掉本机和设备名称，设备地址换成哈希，
+    // 仍能看出各项设置对应同一台设备，保留排查需要的选项
+    pub fn redacted(&self) -> Settings {
+        let mut settings = self.clone();
+        settings.device_name = REDACTED.to_string();
+        for device in &mut settings.trusted_devices {
+            device.address = Self::redact_address(&device.address);
+            if device.name.is_some() {
+                device.name = Some(REDACTED.to_string());
+            }
+        }
+        let rules = &mut settings.rules;
+        rules.primary.devices = rules
+            .primary
+            .devices
+            .drain()
+            .map(|(address, mode)| (Self::redact_address(&address), mode))
+            .collect();
+        rules.transforms.devices = rules
+            .transforms
+            .devices
+            .drain()
+            .map(|(address, pipelines)| (Self::redact_address(&address), pipelines))
+            .collect();
+        settings
+    }
+
+    // 诊断信息中代替设备地址的哈希，不区分地址大小写，诊断包中各文件使用同一个哈希
+    pub fn redact_address(address: &str) -> String {
+        format!("device-{:016x}", xxh3_64(address.to_ascii_uppercase().as_bytes()))
+    }
+
+    // 移除设备，返回该设备是否存在
+    pub fn forget_device(&mut self, address: &str) -> bool {
+        let before = self.trusted_devices.len();
+        self.trusted_devices.retain(|device| device.address != address);
+        self.trusted_devices.len() != before
+    }
+
+    // 检查所有字段，有问题时按字段返回全部错误
+    pub fn validate(&self) -> Result<(), PastoError> {
+        let mut errors = Vec::new();
+
+        let name = self.device_name.trim();
+        if name.is_empty() {
+            errors.push(FieldError::new("device_name", "must not be empty"));
+        } else if name.len() > MAX_DEVICE_NAME_BYTES {
+            errors.push(FieldError::new(
+                "device_name",
+                format!("must be at most {} bytes, got {}", MAX_DEVICE_NAME_BYTES, name.len()),
+            ));
+        }
+        if self.adapter.as_deref().is_some_and(|adapter| adapter.trim().is_empty()) {
+            errors.push(FieldError::new("adapter", "must not be empty when set"));
+        }
+        for (i, device) in self.trusted_devices.iter().enumerate() {
+            if device.address.trim().is_empty() {
+                errors.push(FieldError::new(
+                    format!("trusted_devices.{}.address", i),
+                    "must not be empty",
+                ));
+            }
+        }
+        for (field, secs) in [
+            ("scan.scan_secs", self.scan.scan_secs),
+            ("scan.connect_scan_secs", self.scan.connect_scan_secs),
+        ] {
+            if !SCAN_SECS_RANGE.contains(&secs) {
+                errors.push(FieldError::new(
+                    field,
+                    format!(
+                        "must be between {} and {}, got {}",
+                        SCAN_SECS_RANGE.start(),
+                        SCAN_SECS_RANGE.end(),
+                        secs
+                    ),
+                ));
+            }
+        }
+        // 快捷键能否注册要到注册时才知道，这里只检查互相冲突
+        let hotkeys = self.hotkeys.entries();
+        for (i, (action, shortcut)) in hotkeys.iter().enumerate() {
+            let Some(shortcut) = shortcut else {
+                continue;
+            };
+            let field = format!("hotkeys.{}", action);
+            if shortcut.trim().is_empty() {
+                errors.push(FieldError::new(field, "must not be empty when set"));
+            } else if let Some((other, _)) = hotkeys[..i].iter().find(|(_, other)| {
+                other.is_some_and(|other| other.eq_ignore_ascii_case(shortcut))
+            }) {
+                errors.push(FieldError::new(
+                    field,
+                    format!("uses the same shortcut as hotkeys.{}", other),
+                ));
+            }
+        }
+        if self.rules.sensitive.ttl_secs == Some(0) {
+            errors.push(FieldError::new("rules.sensitive.ttl_secs", "must be greater than 0"));
+        }
+        for (field, limit) in [
+            ("history.max_items", self.history.max_items),
+            ("history.max_total_bytes", self.history.max_total_bytes),
+            ("history.max_item_bytes", self.history.max_item_bytes),
+        ] {
+            if limit == Some(0) {
+                errors.push(FieldError::new(field, "must be greater than 0"));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(PastoError::invalid_fields("Invalid settings", errors))
+        }
+    }
+}
+
+// 持久化在应用配置目录中的设置
+pub struct SettingsStore {
+    // None 表示只保存在内存中
+    path: Option<PathBuf>,
+    settings: Mutex<Settings>,
+}
+
+impl SettingsStore {
+    // 读取设置文件并升级到当前版本，文件不存在时写入默认设置
+    pub fn open(path: &Path) -> Result<Self, PastoError> {
+        let mut settings = load(path)?;
+        if settings.node_id.is_empty() {
+            settings.node_id = hlc::random_node_id();
+        }
+
+        save(path, &settings)?;
+        Ok(Self {
+            path: Some(path.to_path_buf()),
+            settings: Mutex::new(settings),
+        })
+    }
+
+    // 无法访问配置目录时使用，修改不会保存
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            settings: Mutex::new(Settings {
+                node_id: hlc::random_node_id(),
+                ..Settings::default()
+            }),
+        }
+    }
+
+    pub fn get(&self) -> Result<Settings, PastoError> {
+        self.settings
+            .lock()
+            .map(|settings| settings.clone())
+            .map_err(|e| PastoError::Other(format!("Failed to lock settings: {}", e)))
+    }
+
+    // 按地址或名称移除已信任设备，返回被移除设备的地址
+    pub fn forget_device(&self, id: &str) -> Result<String, PastoError> {
+        let address = match self.get()?.trusted_device(id) {
+            Some(device) => device.address.clone(),
+            None => return Err(PastoError::validation(format!("{} is not a trusted device", id))),
+        };
+        self.update(|settings| {
+            settings.forget_device(&address);
+        })?;
+        Ok(address)
+    }
+
+    // 在当前设置上修改，校验并保存成功后才生效，返回修改前和修改后的设置
+    pub fn update(
+        &self,
+        change: impl FnOnce(&mut Settings),
+    ) -> Result<(Settings, Settings), PastoError> {
+        let mut settings = self
+            .settings
+            .lock()
+            .map_err(|e| PastoError::Other(format!("Failed to lock settings: {}", e)))?;
+
+        let mut updated = settings.clone();
+        change(&mut updated);
+        updated.version = SETTINGS_VERSION;
+        // 前端整体替换设置时可能不带设备标识，保留原来的
+        if updated.node_id.is_empty() {
+            updated.node_id = settings.node_id.clone();
+        }
+        updated.validate()?;
+        if let Some(path) = &self.path {
+            save(path, &updated)?;
+        }
+
+        let previous = std::mem::replace(&mut *settings, updated.clone());
+        Ok((previous, updated))
+    }
+}
+
+// 按 version 逐级升级设置文件；比当前程序更新的版本无法识别，直接报错而不是覆盖
+// 只读取设置文件并升级到当前版本，不写回文件，文件不存在时返回默认设置。
+// 供只需读取设置的命令使用，避免与正在运行的 pastod 或桌面版同时写入
+pub fn load(path: &Path) -> Result<Settings, PastoError> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => {
+            let value: Value = serde_json::from_str(&contents)
+                .map_err(|e| PastoError::storage("Failed to parse settings file", e))?;
+            let value = migrate(value)?;
+            let settings: Settings = serde_json::from_value(value)
+                .map_err(|e| PastoError::storage("Failed to read settings", e))?;
+            settings.validate()?;
+            Ok(settings)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Settings::default()),
+        Err(e) => Err(PastoError::storage("Failed to read settings file", e)),
+    }
+}
+
+fn migrate(mut value: Value) -> Result<Value, PastoError> {
+    let Some(object) = value.as_object_mut() else {
+        return Err(PastoError::validation("Settings file must contain a JSON object"));
+    };
+    let version = object.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if version > SETTINGS_VERSION as u64 {
+        return Err(PastoError::validation(format!(
+            "Settings file version {} is newer than supported version {}",
+            version, SETTINGS_VERSION
+        )));
+    }
+
+    if version < 1 {
+        migrate_v0(object);
+    }
+    // 以后的结构变化在这里添加 if version < N { ... } 升级步骤
+
+    object.insert("version".to_string(), Value::from(SETTINGS_VERSION));
+    Ok(value)
+}
+
+// v0：早期不带 version 的设置文件，扫描时长、BLE 开关和各项规则直接放在顶层。
+// 移到 v1 的分组中；分组里已有的值优先，不会被顶层的旧值覆盖
+fn migrate_v0(object: &mut serde_json::Map<String, Value>) {
+    const MOVES: &[(&str, &str, &str)] = &[
+        ("scan_secs", "scan", "scan_secs"),
+        ("connect_scan_secs", "scan", "connect_scan_secs"),
+        ("ble", "transports", "ble"),
+        ("sensitive", "rules", "sensitive"),
+        ("primary", "rules", "primary"),
+        ("transforms", "rules", "transforms"),
+    ];
+    for (old, group, key) in MOVES {
+        let Some(value) = object.remove(*old) else {
+            continue;
+        };
+        let group = object
+            .entry(group.to_string())
+            .or_insert_with(|| Value::Object(Default::default()));
+        if let Some(group) = group.as_object_mut() {
+            group.entry(key.to_string()).or_insert(value);
+        }
+    }
+}
+
+// 先写临时文件再替换，避免写到一半时退出留下损坏的设置文件
+fn save(path: &Path, settings: &Settings) -> Result<(), PastoError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| PastoError::storage("Failed to create settings directory", e))?;
+    }
+    let contents = serde_json::to_string_pretty(settings)
+        .map_err(|e| PastoError::storage("Failed to serialize settings", e))?;
+    let temp_path = path.with_extension("json.tmp");
+    std::fs::write(&temp_path, contents)
+        .map_err(|e| PastoError::storage("Failed to write settings file", e))?;
+    std::fs::rename(&temp_path, path).map_err(|e| PastoError::storage("Failed to replace settings file", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primary::PrimarySyncMode;
+    use crate::sensitive::SensitiveSyncAction;
+
+    // 每个测试使用单独的目录，避免并行运行时互相干扰
+    fn temp_settings_path(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("pasto-settings-{}-{}", test, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("settings.json")
+    }
+
+    #[test]
+    fn invalid_settings_report_each_field() {
+        let settings = Settings {
+            device_name: " ".to_string(),
+            scan: ScanTimings {
+                scan_secs: 0,
+                connect_scan_secs: 2,
+            },
+            trusted_devices: vec![TrustedDevice {
+                address: "".to_string(),
+                name: None,
+            }],
+            ..Settings::default()
+        };
+        let error = settings.validate().unwrap_err();
+        assert_eq!(error.code(), "validation");
+        let PastoError::Validation { fields, .. } = &error else {
+            panic!("expected a validation error, got {:?}", error);
+        };
+        let names: Vec<&str> = fields.iter().map(|field| field.field.as_str()).collect();
+        assert_eq!(names, ["device_name", "trusted_devices.0.address", "scan.scan_secs"]);
+    }
+
+    #[test]
+    fn rejects_zero_history_limits() {
+        let mut settings = Settings::default();
+        settings.history.max_total_bytes = Some(0);
+        settings.history.max_item_bytes = Some(0);
+
+        let PastoError::Validation { fields, .. } = settings.validate().unwrap_err() else {
+            panic!("expected a validation error");
+        };
+        let names: Vec<&str> = fields.iter().map(|field| field.field.as_str()).collect();
+        assert_eq!(names, ["history.max_total_bytes", "history.max_item_bytes"]);
+    }
+
+    #[test]
+    fn loads_unversioned_settings_file() {
+        let path = temp_settings_path("v0");
+        std::fs::write(
+            &path,
+            r#"{
+                "device_name": "Laptop",
+                "sync_direction": "send_only",
+                "scan_secs": 10,
+                "connect_scan_secs": 5,
+                "ble": true,
+                "sensitive": { "enabled": true, "sync": "block", "ttl_secs": 30 }
+            }"#,
+        )
+        .unwrap();
+
+        let settings = SettingsStore::open(&path).unwrap().get().unwrap();
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert_eq!(settings.device_name, "Laptop");
+        assert_eq!(settings.sync_direction, SyncDirection::SendOnly);
+        assert_eq!(
+            settings.scan,
+            ScanTimings {
+                scan_secs: 10,
+                connect_scan_secs: 5
+            }
+        );
+        assert!(settings.transports.ble);
+        assert_eq!(settings.rules.sensitive.sync, SensitiveSyncAction::Block);
+        assert_eq!(settings.rules.sensitive.ttl_secs, Some(30));
+        assert!(!settings.node_id.is_empty());
+
+        // 升级后的文件按 v1 结构保存，不再有顶层的旧字段
+        let saved: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["version"], SETTINGS_VERSION);
+        assert_eq!(saved["scan"]["scan_secs"], 10);
+        assert!(saved.get("scan_secs").is_none());
+        assert!(saved.get("sensitive").is_none());
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn v0_migration_keeps_grouped_values() {
+        let value = serde_json::json!({
+            "scan_secs": 10,
+            "scan": { "scan_secs": 4 },
+        });
+        let migrated = migrate(value).unwrap();
+        assert_eq!(migrated["scan"]["scan_secs"], 4);
+        assert_eq!(migrated["version"], SETTINGS_VERSION);
+    }
+
+    #[test]
+    fn rejects_newer_settings_file() {
+        let error = migrate(serde_json::json!({ "version": SETTINGS_VERSION + 1 })).unwrap_err();
+        assert_eq!(error.code(), "validation");
+    }
+
+    #[test]
+    fn unreadable_settings_file_reports_storage_code() {
+        let path = temp_settings_path("unreadable");
+        std::fs::write(&path, "{ not json").unwrap();
+
+        let error = SettingsStore::open(&path).err().unwrap();
+        assert_eq!(error.code(), "storage");
+        assert!(error.chain().starts_with("Failed to parse settings file: "));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn finds_trusted_device_by_address_or_name() {
+        let mut settings = Settings::default();
+        settings.trust_device("AA:BB:CC:DD:EE:FF", Some("Phone".to_string()));
+
+        let address = |id| settings.trusted_device(id).map(|device| device.address.as_str());
+        assert_eq!(address("aa:bb:cc:dd:ee:ff"), Some("AA:BB:CC:DD:EE:FF"));
+        assert_eq!(address("phone"), Some("AA:BB:CC:DD:EE:FF"));
+        assert_eq!(address("Tablet"), None);
+    }
+
+    #[test]
+    fn redacted_settings_hash_device_addresses() {
+        let mut settings = Settings::default();
+        settings.trust_device("AA:BB:CC:DD:EE:FF", Some("Phone".to_string()));
+        settings
+            .rules
+            .primary
+            .set_mode("aa:bb:cc:dd:ee:ff".to_string(), PrimarySyncMode::ToPrimary);
+
+        let redacted = settings.redacted();
+        let device = &redacted.trusted_devices[0];
+        assert_eq!(device.name.as_deref(), Some(REDACTED));
+        assert!(device.address.starts_with("device-"));
+        assert!(!serde_json::to_string(&redacted).unwrap().to_ascii_uppercase().contains("AA:BB"));
+        assert_eq!(redacted.rules.primary.mode_for(&device.address), PrimarySyncMode::ToPrimary);
+    }
+
+    #[test]
+    fn load_does_not_write_settings_file() {
+        let path = temp_settings_path("load");
+        assert_eq!(load(&path).unwrap(), Settings::default());
+        assert!(!path.exists());
+
+        std::fs::write(&path, r#"{ "device_name": "Desk" }"#).unwrap();
+        assert_eq!(load(&path).unwrap().device_name, "Desk");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), r#"{ "device_name": "Desk" }"#);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn forgets_trusted_device_by_name() {
+        let store = SettingsStore::in_memory();
+        store
+            .update(|settings| settings.trust_device("AA:BB:CC:DD:EE:FF", Some("Phone".to_string())))
+            .unwrap();
+
+        let error = store.forget_device("Tablet").unwrap_err();
+        assert_eq!(error.to_string(), "Tablet is not a trusted device");
+        assert_eq!(store.forget_device("phone").unwrap(), "AA:BB:CC:DD:EE:FF");
+        assert!(store.get().unwrap().trusted_devices.is_empty());
+    }
+}
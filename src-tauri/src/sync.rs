@@ -0,0 +1,232 @@
+// 桌面应用和 pastod 共用的同步规则：本机选区变化后如何记录历史、是否发送，
+// 收到的内容如何按最后写入者胜出写入剪贴板和历史。
+// 这里不涉及界面，确认、通知和定时清除由调用方各自处理
+use crate::ble::{self, ReceivedClipboard, SendOptions, SyncTarget};
+use crate::clipboard::{ClipboardContent, ClipboardManager};
+use crate::content_kind::{self, ContentClass};
+use crate::history::{HistoryEntry, HistoryStore, NewHistoryEntry};
+use crate::hlc;
+use crate::primary::PrimarySyncMode;
+use crate::sensitive::{self, SensitiveKind, SensitiveSyncAction};
+use crate::settings::Settings;
+use crate::transform::{self, Direction};
+use crate::watcher::Selection;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{error, info};
+
+// 本机选区变化后的同步方式
+#[derive(Debug)]
+pub enum Outgoing {
+    // 不同步：未连接、未开启发送、已暂停或该设备未开启 PRIMARY 同步
+    Skip,
+    // 敏感内容按设置不同步
+    Blocked(SensitiveKind),
+    // 敏感内容需要用户确认后再发送，text 为变换前的原文
+    Confirm { text: String, kind: SensitiveKind },
+    // text 已按发送方向变换
+    Send { text: String, options: SendOptions },
+}
+
+// 写入本机剪贴板的远端内容
+#[derive(Debug)]
+pub struct Applied {
+    pub device: String,
+    // 变换后写入剪贴板的内容
+    pub content: String,
+    pub class: Option<ContentClass>,
+    // 到期后清除的秒数：对方指定的有效期，或本机判定为敏感时的默认有效期
+    pub ttl_secs: Option<u64>,
+    // 对方标记为敏感（带有效期）或看起来像敏感内容，即使本机关闭了检测也不应显示预览
+    pub sensitive: bool,
+    // 记录到历史的条目，按设置不记录或超过单条大小上限时为 None
+    pub entry: Option<HistoryEntry>,
+    // 被覆盖的本机内容看起来敏感时，撤销栈只保留它这么多秒，到期后调用 expire_undo_after
+    pub undo_ttl_secs: Option<u64>,
+}
+
+// 本机选区变化：剪贴板内容按敏感内容策略记录历史，再决定是否同步到已连接设备。
+// PRIMARY 选区变化频繁，敏感内容直接跳过，不请求确认
+pub fn local_change(
+    settings: &Settings,
+    history: &HistoryStore,
+    selection: Selection,
+    content: ClipboardContent,
+    paused: bool,
+) -> Outgoing {
+    let Some(text) = content.text else {
+        return Outgoing::Skip;
+    };
+    let config = &settings.rules.sensitive;
+    let sensitive = content.sensitive.filter(|_| config.enabled);
+
+    if selection == Selection::Clipboard {
+        if let Some(recorded) = config.history_text(sensitive, &text) {
+            let entry = NewHistoryEntry::text(recorded, None)
+                .with_class(config.history_class(sensitive, content.kind.clone()));
+            if let Err(e) = history.record(entry) {
+                error!("Failed to record clipboard history: {}", e.chain());
+            }
+        }
+    }
+
+    let Some(address) = ble::connected_address() else {
+        return Outgoing::Skip;
+    };
+    if !settings.sends() || paused {
+        return Outgoing::Skip;
+    }
+    let target = match selection {
+        Selection::Clipboard => SyncTarget::Clipboard,
+        Selection::Primary => match settings.rules.primary.mode_for(&address) {
+            PrimarySyncMode::Off => return Outgoing::Skip,
+            PrimarySyncMode::ToClipboard => SyncTarget::Clipboard,
+            PrimarySyncMode::ToPrimary => SyncTarget::Primary,
+        },
+    };
+
+    let ttl_secs = match (sensitive, config.sync) {
+        (None, _) => None,
+        (Some(kind), _) if selection == Selection::Primary => return Outgoing::Blocked(kind),
+        (Some(kind), SensitiveSyncAction::Block) => return Outgoing::Blocked(kind),
+        (Some(kind), SensitiveSyncAction::Confirm) => return Outgoing::Confirm { text, kind },
+        // 敏感内容附带有效期，接收方到期后自动清除
+        (Some(_), SensitiveSyncAction::Allow) => config.ttl_secs,
+    };
+    Outgoing::Send {
+        text: apply_transforms(settings, Direction::Outgoing, &text),
+        options: SendOptions {
+            timestamp: Some(content.timestamp),
+            ttl_secs,
+            target,
+            kind: content.kind,
+        },
+    }
+}
+
+// 将收到的内容变换后写入本机。发往 PRIMARY 的内容只写入选区，不记录历史；
+// 比本机当前内容更早的剪贴板内容直接丢弃。写入剪贴板后返回 Some，
+// 不检查同步方向和暂停状态，由调用方决定是否接收
+pub fn apply_received(
+    settings: &Settings,
+    clipboard: &Mutex<ClipboardManager>,
+    history: &HistoryStore,
+    mut received: ReceivedClipboard,
+) -> Option<Applied> {
+    received.content = apply_transforms(settings, Direction::Incoming, &received.content);
+
+    if received.target == SyncTarget::Primary {
+        hlc::observe(&received.timestamp);
+        let result = match clipboard.lock() {
+            Ok(manager) => manager.set_primary_text(&received.content),
+            Err(e) => Err(format!("Failed to lock clipboard manager: {}", e).into()),
+        };
+        if let Err(e) = result {
+            error!("Failed to apply received PRIMARY selection: {}", e);
+        }
+        return None;
+    }
+
+    // 最后写入者胜出：比本机当前内容更早的远端内容直接丢弃
+    if !hlc::accept_remote_write(&received.timestamp) {
+        info!(
+            "Ignoring clipboard from {}: older than current clipboard ({:?})",
+            received.device, received.timestamp
+        );
+        return None;
+    }
+    let config = &settings.rules.sensitive;
+    // 被覆盖的本机内容若是敏感内容，撤销栈中也只保留默认有效期
+    let undo_ttl_secs = config.ttl_secs.filter(|_| config.enabled);
+    let result = match clipboard.lock() {
+        Ok(manager) => {
+            manager.set_remote_text(&received.content, undo_ttl_secs.map(Duration::from_secs))
+        }
+        Err(e) => Err(format!("Failed to lock clipboard manager: {}", e).into()),
+    };
+    let undo_expires = match result {
+        Ok(expires) => expires,
+        Err(e) => {
+            error!("Failed to apply received clipboard: {}", e);
+            return None;
+        }
+    };
+
+    let detected = sensitive::classify(&received.content);
+    let sensitive = detected.filter(|_| config.enabled);
+    // 对方未指定有效期时，本机判定为敏感的内容使用默认有效期
+    let ttl_secs = received.ttl_secs.or_else(|| sensitive.and(config.ttl_secs));
+    // 对方没有附带内容类型时在本机检测
+    let class = received
+        .kind
+        .or_else(|| Some(content_kind::detect(&received.content)));
+
+    let entry = config.history_text(sensitive, &received.content).and_then(|recorded| {
+        let entry = NewHistoryEntry::text(recorded, Some(received.device.clone()))
+            .with_class(config.history_class(sensitive, class.clone()));
+        history.record(entry).unwrap_or_else(|e| {
+            error!("Failed to record received clipboard: {}", e.chain());
+            None
+        })
+    });
+
+    Some(Applied {
+        sensitive: received.ttl_secs.is_some() || detected.is_some(),
+        device: received.device,
+        content: received.content,
+        class,
+        ttl_secs,
+        entry,
+        undo_ttl_secs: undo_ttl_secs.filter(|_| undo_expires),
+    })
+}
+
+// 到期后若剪贴板仍是该内容则清空，由调用方放到各自的异步运行时中执行
+pub async fn clear_after_ttl(clipboard: Arc<Mutex<ClipboardManager>>, content: String, ttl_secs: u64) {
+    tokio::time::sleep(Duration::from_secs(ttl_secs)).await;
+    let result = match clipboard.lock() {
+        Ok(manager) => manager.clear_if_unchanged(&content),
+        Err(e) => Err(format!("Failed to lock clipboard manager: {}", e).into()),
+    };
+    match result {
+        Ok(true) => info!("Cleared received clipboard item after {}s", ttl_secs),
+        Ok(false) => {}
+        Err(e) => error!("Failed to clear expired clipboard item: {}", e),
+    }
+}
+
+// 到期后丢弃撤销栈中过期的敏感内容
+pub async fn expire_undo_after(clipboard: Arc<Mutex<ClipboardManager>>, ttl_secs: u64) {
+    tokio::time::sleep(Duration::from_secs(ttl_secs)).await;
+    let result = match clipboard.lock() {
+        Ok(manager) => manager.expire_undo(),
+        Err(e) => Err(format!("Failed to lock clipboard manager: {}", e).into()),
+    };
+    if let Err(e) = result {
+        error!("Failed to expire undo history: {}", e);
+    }
+}
+
+// 按当前连接设备的配置对内容做变换
+pub fn apply_transforms(settings: &Settings, direction: Direction, text: &str) -> String {
+    let device = ble::connected_address();
+    transform::apply(settings.rules.transforms.pipeline(direction, device.as_deref()), text)
+}
+
+// 主动发送一段文本前的检查：敏感内容按设置拦截或附带有效期，返回变换后的文本和发送选项
+pub fn prepare_send(settings: &Settings, text: &str) -> Result<(String, SendOptions), String> {
+    if !settings.sends() {
+        return Err("Sending is disabled in settings".to_string());
+    }
+    let config = &settings.rules.sensitive;
+    let sensitive = sensitive::classify(text).filter(|_| config.enabled);
+    if let (Some(kind), SensitiveSyncAction::Block) = (sensitive, config.sync) {
+        return Err(format!("Not sending text: looks like a {}", kind.label()));
+    }
+    let options = SendOptions {
+        ttl_secs: sensitive.and(config.ttl_secs),
+        kind: Some(content_kind::detect(text)),
+        ..Default::default()
+    };
+    Ok((apply_transforms(settings, Direction::Outgoing, text), options))
+}
@@ -0,0 +1,278 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use unicode_normalization::UnicodeNormalization;
+
+// URL 中常见的跟踪参数，以 * 结尾的按前缀匹配
+const TRACKING_PARAMS: &[&str] = &[
+    "utm_*", "fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "yclid", "mc_cid",
+    "mc_eid", "igshid", "_hsenc", "_hsmi", "mkt_tok", "ref_src", "spm",
+];
+
+// 换行符风格，Native 表示本机系统的习惯（Windows 为 CRLF，其它为 LF）
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEnding {
+    Native,
+    Lf,
+    Crlf,
+}
+
+// 单个文本变换
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Transform {
+    // 去掉每行行尾空白以及首尾空行
+    Trim,
+    NormalizeLineEndings { style: LineEnding },
+    UnicodeNfc,
+    StripUrlTracking,
+    // 整段内容是 JSON 对象或数组时格式化输出
+    PrettyJson,
+    // 弯引号、长破折号、不间断空格等替换为普通字符，去掉零宽字符
+    PlainText,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    // 发送给其他设备之前
+    Outgoing,
+    // 收到后写入本机剪贴板之前
+    Incoming,
+}
+
+// 一组按顺序执行的变换
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct TransformPipelines {
+    pub outgoing: Vec<Transform>,
+    pub incoming: Vec<Transform>,
+}
+
+impl TransformPipelines {
+    pub fn get(&self, direction: Direction) -> &[Transform] {
+        match direction {
+            Direction::Outgoing => &self.outgoing,
+            Direction::Incoming => &self.incoming,
+        }
+    }
+}
+
+// 默认流水线，以及按设备地址覆盖的流水线
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct TransformConfig {
+    pub default: TransformPipelines,
+    pub devices: HashMap<String, TransformPipelines>,
+}
+
+impl TransformConfig {
+    // 设备有单独配置时完全使用设备的配置，否则使用默认配置。设备地址不区分大小写
+    pub fn pipeline(&self, direction: Direction, device: Option<&str>) -> &[Transform] {
+        device
+            .and_then(|device| {
+                self.devices
+                    .iter()
+                    .find(|(address, _)| address.eq_ignore_ascii_case(device))
+                    .map(|(_, pipelines)| pipelines)
+            })
+            .unwrap_or(&self.default)
+            .get(direction)
+    }
+}
+
+pub fn apply(transforms: &[Transform], text: &str) -> String {
+    transforms
+        .iter()
+        .fold(text.to_string(), |text, transform| apply_one(*transform, &text))
+}
+
+fn apply_one(transform: Transform, text: &str) -> String {
+    match transform {
+        Transform::Trim => trim(text),
+        Transform::NormalizeLineEndings { style } => normalize_line_endings(text, style),
+        Transform::UnicodeNfc => text.nfc().collect(),
+        Transform::StripUrlTracking => strip_url_tracking(text),
+        Transform::PrettyJson => pretty_json(text),
+        Transform::PlainText => plain_text(text),
+    }
+}
+
+fn trim(text: &str) -> String {
+    let line_ending = if text.contains("\r\n") { "\r\n" } else { "\n" };
+    text.lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join(line_ending)
+        .trim_matches(|c| c == '\r' || c == '\n')
+        .to_string()
+}
+
+fn normalize_line_endings(text: &str, style: LineEnding) -> String {
+    let crlf = match style {
+        LineEnding::Native => cfg!(target_os = "windows"),
+        LineEnding::Lf => false,
+        LineEnding::Crlf => true,
+    };
+    let lf = text.replace("\r\n", "\n").replace('\r', "\n");
+    if crlf {
+        lf.replace('\n', "\r\n")
+    } else {
+        lf
+    }
+}
+
+// 逐个处理以空白分隔的 http(s) 链接，保留原有的空白
+fn strip_url_tracking(text: &str) -> String {
+    text.split_inclusive(char::is_whitespace)
+        .map(|piece| {
+            let token = piece.trim_end();
+            let whitespace = &piece[token.len()..];
+            if token.starts_with("http://") || token.starts_with("https://") {
+                format!("{}{}", strip_tracking_params(token), whitespace)
+            } else {
+                piece.to_string()
+            }
+        })
+        .collect()
+}
+
+fn strip_tracking_params(url: &str) -> String {
+    let (without_fragment, fragment) = match url.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (url, None),
+    };
+    let Some((base, query)) = without_fragment.split_once('?') else {
+        return url.to_string();
+    };
+
+    let kept: Vec<&str> = query
+        .split('&')
+        .filter(|param| {
+            let key = param.split('=').next().unwrap_or_default();
+            !param.is_empty() && !is_tracking_param(key)
+        })
+        .collect();
+
+    let mut result = base.to_string();
+    if !kept.is_empty() {
+        result.push('?');
+        result.push_str(&kept.join("&"));
+    }
+    if let Some(fragment) = fragment {
+        result.push('#');
+        result.push_str(fragment);
+    }
+    result
+}
+
+fn is_tracking_param(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    TRACKING_PARAMS.iter().any(|param| match param.strip_suffix('*') {
+        Some(prefix) => key.starts_with(prefix),
+        None => key == *param,
+    })
+}
+
+fn pretty_json(text: &str) -> String {
+    let trimmed = text.trim();
+    if !(trimmed.starts_with('{') || trimmed.starts_with('[')) {
+        return text.to_string();
+    }
+    serde_json::from_str::<serde_json::Value>(trimmed)
+        .ok()
+        .and_then(|value| serde_json::to_string_pretty(&value).ok())
+        .unwrap_or_else(|| text.to_string())
+}
+
+fn plain_text(text: &str) -> String {
+    text.chars()
+        .filter_map(|c| match c {
+            '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{2032}' => Some('\''),
+            '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{2033}' => Some('"'),
+            '\u{2013}' | '\u{2014}' | '\u{2212}' => Some('-'),
+            '\u{00A0}' | '\u{2007}' | '\u{202F}' => Some(' '),
+            '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}' => None,
+            c => Some(c),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trim_keeps_crlf_line_endings() {
+        assert_eq!(trim("\r\n  first  \r\nsecond\t\r\n\r\n"), "  first\r\nsecond");
+    }
+
+    #[test]
+    fn normalizes_lone_carriage_returns() {
+        let text = "a\rb\r\nc\nd";
+        assert_eq!(normalize_line_endings(text, LineEnding::Lf), "a\nb\nc\nd");
+        assert_eq!(normalize_line_endings(text, LineEnding::Crlf), "a\r\nb\r\nc\r\nd");
+    }
+
+    #[test]
+    fn strips_tracking_params() {
+        assert_eq!(
+            strip_tracking_params("https://example.com/a?utm_source=x&UTM_Medium=y&fbclid=z"),
+            "https://example.com/a"
+        );
+        assert_eq!(
+            strip_tracking_params("https://example.com/a?id=1&utm_campaign=x&page=2"),
+            "https://example.com/a?id=1&page=2"
+        );
+        assert_eq!(
+            strip_tracking_params("https://example.com/a?gclid=x&q=rust#section"),
+            "https://example.com/a?q=rust#section"
+        );
+        assert_eq!(
+            strip_tracking_params("https://example.com/a?utm_source=x#section"),
+            "https://example.com/a#section"
+        );
+    }
+
+    #[test]
+    fn strips_tracking_only_from_urls() {
+        assert_eq!(
+            strip_url_tracking("see https://example.com/?utm_source=x\nand ?utm_source=y"),
+            "see https://example.com/\nand ?utm_source=y"
+        );
+    }
+
+    #[test]
+    fn pretty_json_leaves_invalid_json_unchanged() {
+        assert_eq!(pretty_json("{\"a\":1,}"), "{\"a\":1,}");
+        assert_eq!(pretty_json("[1, 2"), "[1, 2");
+        assert_eq!(pretty_json("{\"a\":[1]}"), "{\n  \"a\": [\n    1\n  ]\n}");
+    }
+
+    #[test]
+    fn plain_text_removes_zero_width_characters() {
+        assert_eq!(plain_text("pa\u{200B}ss\u{200D}wo\u{FEFF}rd"), "password");
+        assert_eq!(plain_text("\u{201C}quoted\u{201D}\u{00A0}\u{2014} it\u{2019}s"), "\"quoted\" - it's");
+    }
+
+    #[test]
+    fn device_pipeline_overrides_default() {
+        let mut config = TransformConfig::default();
+        config.default.outgoing = vec![Transform::Trim];
+        config.devices.insert(
+            "AA:BB:CC:DD:EE:FF".to_string(),
+            TransformPipelines {
+                outgoing: vec![Transform::PlainText],
+                incoming: Vec::new(),
+            },
+        );
+
+        assert_eq!(config.pipeline(Direction::Outgoing, None), [Transform::Trim]);
+        assert_eq!(config.pipeline(Direction::Outgoing, Some("11:22:33:44:55:66")), [Transform::Trim]);
+        assert_eq!(
+            config.pipeline(Direction::Outgoing, Some("aa:bb:cc:dd:ee:ff")),
+            [Transform::PlainText]
+        );
+        // 设备配置完全覆盖默认配置，没有配置的方向不回退到默认
+        assert!(config.pipeline(Direction::Incoming, Some("AA:BB:CC:DD:EE:FF")).is_empty());
+    }
+}
@@ -0,0 +1,656 @@
+use crate::clipboard::{ClipboardContent, ClipboardManager};
+use crate::error::PastoError;
+use crate::logging;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, info_span, warn};
+
+// 不支持事件通知的平台上，退回到轮询的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+// 拖动选择文本时 PRIMARY 会连续变化，停止变化这么久之后才读取
+const PRIMARY_DEBOUNCE: Duration = Duration::from_millis(500);
+
+// 发生变化的选区
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    Clipboard,
+    Primary,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WatchOptions {
+    // 是否同时监听 PRIMARY 选区（X11 / Wayland 有意义）
+    pub watch_primary: bool,
+}
+
+// 启动选区监听线程，每当选区可能发生变化时通过 channel 发出唤醒信号。
+// Wayland 下订阅 data-control 的 selection 事件，X11 下订阅 XFixes 的
+// selection owner 变化事件，其它情况每秒轮询一次。
+pub fn spawn_selection_watcher(options: WatchOptions) -> Receiver<Selection> {
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let _span = info_span!("clipboard").entered();
+        #[cfg(target_os = "linux")]
+        {
+            if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+                debug!("Using Wayland data-control selection watcher");
+                match wayland::watch(options, &tx) {
+                    // 接收端已关闭，无需再监听
+                    Ok(()) => return,
+                    Err(e) => warn!(
+                        "Wayland watcher unavailable, falling back to polling: {}",
+                        e
+                    ),
+                }
+            } else if std::env::var_os("DISPLAY").is_some() {
+                debug!("Using XFixes selection watcher");
+                match x11::watch(options, &tx) {
+                    // 接收端已关闭，无需再监听
+                    Ok(()) => return,
+                    Err(e) => warn!(
+                        "XFixes watcher unavailable, falling back to polling: {}",
+                        e
+                    ),
+                }
+            }
+        }
+
+        debug!(
+            "Polling clipboard every {:?}",
+            POLL_INTERVAL
+        );
+        poll(options, &tx);
+    });
+
+    rx
+}
+
+// 启动剪贴板监听：选区变化时唤醒 ClipboardManager 检查内容，内容确实变化时回调 on_change。
+// PRIMARY 的变化经过防抖，只在选择停止后检查一次
+pub fn spawn_clipboard_watcher<F>(
+    manager: Arc<Mutex<ClipboardManager>>,
+    options: WatchOptions,
+    on_change: F,
+) where
+    F: Fn(Selection, ClipboardContent) + Send + 'static,
+{
+    let wakeups = spawn_selection_watcher(options);
+
+    thread::spawn(move || {
+        let _span = info_span!("clipboard").entered();
+        let mut primary_due: Option<Instant> = None;
+        loop {
+            let wakeup = match primary_due {
+                Some(due) => {
+                    match wakeups.recv_timeout(due.saturating_duration_since(Instant::now())) {
+                        Ok(selection) => Some(selection),
+                        Err(RecvTimeoutError::Timeout) => None,
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+                None => match wakeups.recv() {
+                    Ok(selection) => Some(selection),
+                    Err(_) => return,
+                },
+            };
+
+            let selection = match wakeup {
+                Some(Selection::Primary) => {
+                    primary_due = Some(Instant::now() + PRIMARY_DEBOUNCE);
+                    continue;
+                }
+                Some(Selection::Clipboard) => Selection::Clipboard,
+                // 防抖时间到，PRIMARY 已稳定
+                None => {
+                    primary_due = None;
+                    Selection::Primary
+                }
+            };
+
+            let result = match manager.lock() {
+                Ok(manager) => match selection {
+                    Selection::Clipboard => manager.check_for_changes(),
+                    Selection::Primary => manager.check_primary_changes(),
+                },
+                Err(e) => Err(format!("Failed to lock clipboard manager: {}", e).into()),
+            };
+
+            match result {
+                Ok(Some(content)) => {
+                    if let Some(text) = &content.text {
+                        debug!("{:?} changed: {}", selection, logging::contents(text));
+                    }
+                    on_change(selection, content)
+                }
+                Ok(None) => {}
+                // 图片等非文本内容，不需要提示
+                Err(PastoError::ClipboardEmpty) => debug!("{:?} does not contain text", selection),
+                Err(e) => warn!("{}", e),
+            }
+        }
+    });
+}
+
+fn poll(options: WatchOptions, tx: &Sender<Selection>) {
+    loop {
+        if tx.send(Selection::Clipboard).is_err() {
+            return;
+        }
+        if options.watch_primary && tx.send(Selection::Primary).is_err() {
+            return;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod x11 {
+    use super::{Selection, WatchOptions};
+    use std::sync::mpsc::Sender;
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xfixes::{ConnectionExt as _, SelectionEventMask};
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt as _, CreateWindowAux, WindowClass};
+    use x11rb::protocol::Event;
+    use x11rb::COPY_FROM_PARENT;
+
+    // 阻塞运行，直到接收端关闭（返回 Ok）或 X 连接出错（返回 Err）
+    pub fn watch(options: WatchOptions, tx: &Sender<Selection>) -> Result<(), String> {
+        let (conn, screen_num) =
+            x11rb::connect(None).map_err(|e| format!("Failed to connect to X server: {}", e))?;
+
+        // 使用 XFixes 之前必须先协商版本
+        conn.xfixes_query_version(5, 0)
+            .map_err(|e| format!("Failed to query XFixes version: {}", e))?
+            .reply()
+            .map_err(|e| format!("XFixes extension not available: {}", e))?;
+
+        let root = conn.setup().roots[screen_num].root;
+        let window = conn
+            .generate_id()
+            .map_err(|e| format!("Failed to allocate window id: {}", e))?;
+        conn.create_window(
+            COPY_FROM_PARENT as u8,
+            window,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            COPY_FROM_PARENT,
+            &CreateWindowAux::new(),
+        )
+        .map_err(|e| format!("Failed to create watcher window: {}", e))?;
+
+        let clipboard = conn
+            .intern_atom(false, b"CLIPBOARD")
+            .map_err(|e| format!("Failed to intern CLIPBOARD atom: {}", e))?
+            .reply()
+            .map_err(|e| format!("Failed to intern CLIPBOARD atom: {}", e))?
+            .atom;
+        let primary: u32 = AtomEnum::PRIMARY.into();
+
+        let mask = SelectionEventMask::SET_SELECTION_OWNER
+            | SelectionEventMask::SELECTION_WINDOW_DESTROY
+            | SelectionEventMask::SELECTION_CLIENT_CLOSE;
+        conn.xfixes_select_selection_input(window, clipboard, mask)
+            .map_err(|e| format!("Failed to select CLIPBOARD events: {}", e))?;
+        if options.watch_primary {
+            conn.xfixes_select_selection_input(window, primary, mask)
+                .map_err(|e| format!("Failed to select PRIMARY events: {}", e))?;
+        }
+        conn.flush()
+            .map_err(|e| format!("Failed to flush X connection: {}", e))?;
+
+        // 启动时先检查一次当前内容
+        if tx.send(Selection::Clipboard).is_err() {
+            return Ok(());
+        }
+
+        loop {
+            let event = conn
+                .wait_for_event()
+                .map_err(|e| format!("X connection error: {}", e))?;
+
+            let Event::XfixesSelectionNotify(notify) = event else {
+                continue;
+            };
+
+            let selection = if notify.selection == clipboard {
+                Selection::Clipboard
+            } else if notify.selection == primary {
+                Selection::Primary
+            } else {
+                continue;
+            };
+
+            if tx.send(selection).is_err() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod wayland {
+    use super::{Selection, WatchOptions};
+    use std::sync::mpsc::Sender;
+    use wayland_client::globals::{registry_queue_init, GlobalListContents};
+    use wayland_client::protocol::{wl_registry, wl_seat::WlSeat};
+    use wayland_client::{event_created_child, Connection, Dispatch, Proxy, QueueHandle};
+    use wayland_protocols::ext::data_control::v1::client::{
+        ext_data_control_device_v1::{self, ExtDataControlDeviceV1},
+        ext_data_control_manager_v1::ExtDataControlManagerV1,
+        ext_data_control_offer_v1::ExtDataControlOfferV1,
+    };
+    use wayland_protocols_wlr::data_control::v1::client::{
+        zwlr_data_control_device_v1::{self, ZwlrDataControlDeviceV1},
+        zwlr_data_control_manager_v1::ZwlrDataControlManagerV1,
+        zwlr_data_control_offer_v1::ZwlrDataControlOfferV1,
+    };
+
+    struct State {
+        tx: Sender<Selection>,
+        watch_primary: bool,
+        // 接收端已关闭，无需再监听
+        closed: bool,
+        // 合成器发来 finished，data device 已失效
+        device_finished: bool,
+    }
+
+    impl State {
+        fn notify(&mut self, selection: Selection) {
+            if selection == Selection::Primary && !self.watch_primary {
+                return;
+            }
+            if self.tx.send(selection).is_err() {
+                self.closed = true;
+            }
+        }
+    }
+
+    // 阻塞运行，直到接收端关闭（返回 Ok），或 Wayland 连接出错、data device
+    // 被合成器结束（返回 Err，调用方退回轮询）
+    pub fn watch(options: WatchOptions, tx: &Sender<Selection>) -> Result<(), String> {
+        let conn = Connection::connect_to_env()
+            .map_err(|e| format!("Failed to connect to Wayland compositor: {}", e))?;
+        let (globals, mut queue) = registry_queue_init::<State>(&conn)
+            .map_err(|e| format!("Failed to list Wayland globals: {}", e))?;
+        let qh = queue.handle();
+
+        let seat: WlSeat = globals
+            .bind(&qh, 1..=1, ())
+            .map_err(|e| format!("No Wayland seat available: {}", e))?;
+
+        // 优先使用 ext-data-control，其次 wlr-data-control（v2 起支持 PRIMARY）
+        if let Ok(manager) = globals.bind::<ExtDataControlManagerV1, _, _>(&qh, 1..=1, ()) {
+            manager.get_data_device(&seat, &qh, ());
+        } else {
+            let manager: ZwlrDataControlManagerV1 = globals
+                .bind(&qh, 1..=2, ())
+                .map_err(|e| format!("Compositor does not support data-control: {}", e))?;
+            manager.get_data_device(&seat, &qh, ());
+        }
+
+        let mut state = State {
+            tx: tx.clone(),
+            watch_primary: options.watch_primary,
+            closed: false,
+            device_finished: false,
+        };
+
+        while !state.closed {
+            queue
+                .blocking_dispatch(&mut state)
+                .map_err(|e| format!("Wayland connection error: {}", e))?;
+            if state.device_finished {
+                return Err("Data control device was finished by the compositor".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for State {
+        fn event(
+            _: &mut Self,
+            _: &wl_registry::WlRegistry,
+            _: wl_registry::Event,
+            _: &GlobalListContents,
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+        }
+    }
+
+    impl Dispatch<WlSeat, ()> for State {
+        fn event(
+            _: &mut Self,
+            _: &WlSeat,
+            _: <WlSeat as Proxy>::Event,
+            _: &(),
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+        }
+    }
+
+    impl Dispatch<ExtDataControlManagerV1, ()> for State {
+        fn event(
+            _: &mut Self,
+            _: &ExtDataControlManagerV1,
+            _: <ExtDataControlManagerV1 as Proxy>::Event,
+            _: &(),
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+        }
+    }
+
+    impl Dispatch<ExtDataControlOfferV1, ()> for State {
+        fn event(
+            _: &mut Self,
+            _: &ExtDataControlOfferV1,
+            _: <ExtDataControlOfferV1 as Proxy>::Event,
+            _: &(),
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+        }
+    }
+
+    impl Dispatch<ExtDataControlDeviceV1, ()> for State {
+        fn event(
+            state: &mut Self,
+            device: &ExtDataControlDeviceV1,
+            event: ext_data_control_device_v1::Event,
+            _: &(),
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+            // 这里只关心"选区变了"，内容由 ClipboardManager 自己读取，offer 用完即销毁
+            match event {
+                ext_data_control_device_v1::Event::Selection { id } => {
+                    if let Some(offer) = id {
+                        offer.destroy();
+                    }
+                    state.notify(Selection::Clipboard);
+                }
+                ext_data_control_device_v1::Event::PrimarySelection { id } => {
+                    if let Some(offer) = id {
+                        offer.destroy();
+                    }
+                    state.notify(Selection::Primary);
+                }
+                ext_data_control_device_v1::Event::Finished => {
+                    device.destroy();
+                    state.device_finished = true;
+                }
+                _ => {}
+            }
+        }
+
+        event_created_child!(State, ExtDataControlDeviceV1, [
+            ext_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ExtDataControlOfferV1, ()),
+        ]);
+    }
+
+    impl Dispatch<ZwlrDataControlManagerV1, ()> for State {
+        fn event(
+            _: &mut Self,
+            _: &ZwlrDataControlManagerV1,
+            _: <ZwlrDataControlManagerV1 as Proxy>::Event,
+            _: &(),
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+        }
+    }
+
+    impl Dispatch<ZwlrDataControlOfferV1, ()> for State {
+        fn event(
+            _: &mut Self,
+            _: &ZwlrDataControlOfferV1,
+            _: <ZwlrDataControlOfferV1 as Proxy>::Event,
+            _: &(),
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+        }
+    }
+
+    impl Dispatch<ZwlrDataControlDeviceV1, ()> for State {
+        fn event(
+            state: &mut Self,
+            device: &ZwlrDataControlDeviceV1,
+            event: zwlr_data_control_device_v1::Event,
+            _: &(),
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+            match event {
+                zwlr_data_control_device_v1::Event::Selection { id } => {
+                    if let Some(offer) = id {
+                        offer.destroy();
+                    }
+                    state.notify(Selection::Clipboard);
+                }
+                zwlr_data_control_device_v1::Event::PrimarySelection { id } => {
+                    if let Some(offer) = id {
+                        offer.destroy();
+                    }
+                    state.notify(Selection::Primary);
+                }
+                zwlr_data_control_device_v1::Event::Finished => {
+                    device.destroy();
+                    state.device_finished = true;
+                }
+                _ => {}
+            }
+        }
+
+        event_created_child!(State, ZwlrDataControlDeviceV1, [
+            zwlr_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ZwlrDataControlOfferV1, ()),
+        ]);
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    // 等待下一个 Clipboard 事件，期间收到的 Primary 事件忽略
+    fn expect_clipboard(rx: &Receiver<Selection>, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+            match rx.recv_timeout(left) {
+                Ok(Selection::Clipboard) => return true,
+                Ok(Selection::Primary) => continue,
+                Err(_) => return false,
+            }
+        }
+        false
+    }
+
+    // 在 Xvfb 上抢占选区所有权，模拟其他程序复制或选择文本
+    struct X11Selector {
+        conn: x11rb::rust_connection::RustConnection,
+        window: u32,
+        clipboard: u32,
+    }
+
+    impl X11Selector {
+        fn connect() -> Self {
+            use x11rb::connection::Connection;
+            use x11rb::protocol::xproto::{ConnectionExt as _, CreateWindowAux, WindowClass};
+
+            let (conn, screen_num) = x11rb::connect(None).expect("failed to connect to Xvfb");
+            let root = conn.setup().roots[screen_num].root;
+            let window = conn.generate_id().unwrap();
+            conn.create_window(
+                x11rb::COPY_FROM_PARENT as u8,
+                window,
+                root,
+                0,
+                0,
+                1,
+                1,
+                0,
+                WindowClass::INPUT_ONLY,
+                x11rb::COPY_FROM_PARENT,
+                &CreateWindowAux::new(),
+            )
+            .unwrap();
+            let clipboard = conn.intern_atom(false, b"CLIPBOARD").unwrap().reply().unwrap().atom;
+            Self {
+                conn,
+                window,
+                clipboard,
+            }
+        }
+
+        fn take(&self, selection: Selection) {
+            use x11rb::connection::Connection;
+            use x11rb::protocol::xproto::{AtomEnum, ConnectionExt as _};
+
+            let atom = match selection {
+                Selection::Clipboard => self.clipboard,
+                Selection::Primary => AtomEnum::PRIMARY.into(),
+            };
+            self.conn
+                .set_selection_owner(self.window, atom, x11rb::CURRENT_TIME)
+                .unwrap();
+            self.conn.flush().unwrap();
+        }
+    }
+
+    // 需要 Xvfb：cargo test -- --ignored x11
+    #[test]
+    #[ignore = "needs Xvfb"]
+    fn x11_watcher_reports_selection_owner_changes() {
+        let _xvfb = crate::xvfb::Xvfb::start();
+        let (tx, rx) = mpsc::channel();
+        let options = WatchOptions {
+            watch_primary: true,
+        };
+        thread::spawn(move || x11::watch(options, &tx));
+        let timeout = Duration::from_secs(2);
+
+        // 启动后先检查一次当前内容
+        assert_eq!(rx.recv_timeout(timeout), Ok(Selection::Clipboard));
+
+        let selector = X11Selector::connect();
+        selector.take(Selection::Clipboard);
+        assert_eq!(rx.recv_timeout(timeout), Ok(Selection::Clipboard));
+        selector.take(Selection::Primary);
+        assert_eq!(rx.recv_timeout(timeout), Ok(Selection::Primary));
+
+        // 所有者退出时内容随之消失，同样需要通知
+        drop(selector);
+        let mut remaining: Vec<Selection> =
+            (0..2).filter_map(|_| rx.recv_timeout(timeout).ok()).collect();
+        remaining.sort_by_key(|selection| *selection == Selection::Primary);
+        assert_eq!(remaining, [Selection::Clipboard, Selection::Primary]);
+    }
+
+    #[test]
+    #[ignore = "needs Xvfb"]
+    fn x11_watcher_ignores_primary_unless_enabled() {
+        let _xvfb = crate::xvfb::Xvfb::start();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || x11::watch(WatchOptions::default(), &tx));
+        let timeout = Duration::from_secs(2);
+        assert_eq!(rx.recv_timeout(timeout), Ok(Selection::Clipboard));
+
+        let selector = X11Selector::connect();
+        selector.take(Selection::Primary);
+        selector.take(Selection::Clipboard);
+        assert_eq!(rx.recv_timeout(timeout), Ok(Selection::Clipboard));
+        assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+    }
+
+    // 拖动选择时 PRIMARY 连续变化，只在停止变化 PRIMARY_DEBOUNCE 之后读取一次最终内容；
+    // CLIPBOARD 的变化不受防抖影响
+    #[test]
+    #[ignore = "needs Xvfb"]
+    fn x11_primary_changes_are_debounced() {
+        use crate::clipboard::{ClipboardBackend, ContentType, MemoryBackend};
+
+        let _xvfb = crate::xvfb::Xvfb::start();
+        let backend = MemoryBackend::new();
+        let manager = Arc::new(Mutex::new(ClipboardManager::with_backend(Box::new(backend.clone()))));
+        let (tx, rx) = mpsc::channel();
+        let options = WatchOptions {
+            watch_primary: true,
+        };
+        spawn_clipboard_watcher(manager, options, move |selection, content| {
+            let _ = tx.send((selection, content.text, Instant::now()));
+        });
+        // 等待监听线程订阅 XFixes 事件
+        thread::sleep(Duration::from_millis(200));
+
+        let selector = X11Selector::connect();
+        for text in ["p", "pa", "pas", "past", "pasto"] {
+            backend.set_primary(text.as_bytes()).unwrap();
+            selector.take(Selection::Primary);
+            thread::sleep(PRIMARY_DEBOUNCE / 5);
+        }
+        let last_change = Instant::now();
+
+        backend.set(ContentType::Text, b"copied").unwrap();
+        selector.take(Selection::Clipboard);
+        let (selection, text, _) = rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(selection, Selection::Clipboard);
+        assert_eq!(text.as_deref(), Some("copied"));
+
+        let (selection, text, at) = rx.recv_timeout(PRIMARY_DEBOUNCE * 3).unwrap();
+        assert_eq!(selection, Selection::Primary);
+        assert_eq!(text.as_deref(), Some("pasto"));
+        assert!(at >= last_change + PRIMARY_DEBOUNCE - PRIMARY_DEBOUNCE / 5);
+        assert!(rx.recv_timeout(PRIMARY_DEBOUNCE * 2).is_err());
+    }
+
+    // 需要支持 data-control 的无头合成器，例如：
+    // WAYLAND_DISPLAY=wayland-test weston --backend=headless --socket=wayland-test &
+    // WAYLAND_DISPLAY=wayland-test cargo test -- --ignored wayland
+    #[test]
+    #[ignore = "needs a headless Wayland compositor with data-control"]
+    fn wayland_watcher_reports_clipboard_changes() {
+        use wl_clipboard_rs::copy::{MimeType, Options, Source};
+
+        assert!(
+            std::env::var_os("WAYLAND_DISPLAY").is_some(),
+            "WAYLAND_DISPLAY must point at a headless compositor"
+        );
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || wayland::watch(WatchOptions::default(), &tx));
+
+        // 绑定 data device 后合成器会先发送一次当前选区
+        assert!(expect_clipboard(&rx, Duration::from_secs(5)));
+
+        Options::new()
+            .copy(Source::Bytes(b"pasto wayland test".to_vec().into()), MimeType::Text)
+            .expect("failed to set clipboard");
+        assert!(expect_clipboard(&rx, Duration::from_secs(5)));
+    }
+
+    #[test]
+    #[ignore = "needs a headless Wayland compositor with data-control"]
+    fn wayland_watcher_returns_ok_when_receiver_closes() {
+        use wl_clipboard_rs::copy::{MimeType, Options, Source};
+
+        let (tx, rx) = mpsc::channel();
+        let watcher = thread::spawn(move || wayland::watch(WatchOptions::default(), &tx));
+        assert!(expect_clipboard(&rx, Duration::from_secs(5)));
+        drop(rx);
+
+        // 下一次选区变化时发现接收端已关闭
+        Options::new()
+            .copy(Source::Bytes(b"closed".to_vec().into()), MimeType::Text)
+            .expect("failed to set clipboard");
+        assert_eq!(watcher.join().unwrap(), Ok(()));
+    }
+}
@@ -0,0 +1,51 @@
+// 测试用的 Xvfb 虚拟 X server。被测代码通过 DISPLAY 连接 X server，
+// 而环境变量是进程共享的，所以同一时间只运行一个 Xvfb，持有期间其他 X11 测试等待
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+static LOCK: Mutex<()> = Mutex::new(());
+
+// 等待 Xvfb 创建监听 socket 的最长时间
+const START_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct Xvfb {
+    child: Child,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Xvfb {
+    // 在第一个空闲的显示编号上启动 Xvfb，并把 DISPLAY 指向它
+    pub fn start() -> Self {
+        let lock = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let display = (90..200)
+            .find(|n| !Path::new(&format!("/tmp/.X11-unix/X{}", n)).exists())
+            .expect("no free X display number");
+        let child = Command::new("Xvfb")
+            .arg(format!(":{}", display))
+            .args(["-screen", "0", "640x480x24", "-nolisten", "tcp"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("failed to start Xvfb");
+
+        let socket = format!("/tmp/.X11-unix/X{}", display);
+        let deadline = Instant::now() + START_TIMEOUT;
+        while !Path::new(&socket).exists() {
+            assert!(Instant::now() < deadline, "Xvfb did not start in time");
+            thread::sleep(Duration::from_millis(20));
+        }
+        std::env::set_var("DISPLAY", format!(":{}", display));
+
+        Self { child, _lock: lock }
+    }
+}
+
+impl Drop for Xvfb {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
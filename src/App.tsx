@@ -1,5 +1,7 @@
 import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { save } from "@tauri-apps/plugin-dialog";
 import "./App.css";
 
 interface BleDevice {
@@ -8,6 +10,50 @@ interface BleDevice {
   rssi?: number;
 }
 
+interface ClipboardContent {
+  text?: string | null;
+  timestamp: { physical_ms: number; counter: number; node: string };
+  sensitive?: string | null;
+  kind?: { kind: string; language?: string } | null;
+}
+
+interface SensitiveSyncRequest {
+  id: number;
+  kind: string;
+}
+
+// 后端命令返回的错误，code 用于区分错误类型
+interface PastoError {
+  code: string;
+  message: string;
+  details?: string | null;
+  // code 为 validation 时按字段列出的错误
+  fields?: { field: string; message: string }[];
+}
+
+const ERROR_MESSAGES: Record<string, string> = {
+  adapter_missing: "未找到蓝牙适配器",
+  bluetooth_off: "蓝牙未开启",
+  permission_denied: "没有蓝牙权限，请在系统设置中允许 Pasto 使用蓝牙",
+  not_connected: "尚未连接设备",
+  device_not_found: "未找到该设备，请确认设备在附近并已开启 Pasto",
+  service_not_found: "该设备没有提供剪贴板同步服务",
+  characteristic_not_found: "该设备的剪贴板同步服务不完整",
+  timed_out: "蓝牙操作超时",
+  not_supported: "当前平台不支持该蓝牙操作",
+  clipboard_empty: "剪贴板中没有文本",
+  clipboard_busy: "剪贴板正被其他程序占用",
+  clipboard_unsupported: "当前环境不支持访问剪贴板",
+};
+
+function errorMessage(error: unknown): string {
+  const e = error as Partial<PastoError> | null;
+  if (e && typeof e === "object" && typeof e.code === "string") {
+    return ERROR_MESSAGES[e.code] ?? e.message ?? e.code;
+  }
+  return String(error);
+}
+
 function App() {
   const [clipboardText, setClipboardText] = useState("");
   const [bleDevices, setBleDevices] = useState<BleDevice[]>([]);
@@ -19,23 +65,48 @@ function App() {
 
   const showPopup = (msg: string) => setPopupMsg(msg);
 
-  // 监听剪贴板变化
+  // 监听剪贴板变化（由后端推送，已连接设备时后端会自动同步）
+  useEffect(() => {
+    const unlisten = listen<ClipboardContent>("clipboard-changed", (event) => {
+      if (event.payload.text !== undefined && event.payload.text !== null) {
+        setClipboardText(event.payload.text);
+      }
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // 广播状态以后端为准：托盘菜单操作或广播进程退出时都会推送
+  useEffect(() => {
+    invoke<boolean>("get_advertising_status")
+      .then(setIsAdvertising)
+      .catch((error) => console.error("Failed to get advertising status:", error));
+    const unlisten = listen<boolean>("advertising-changed", (event) => {
+      setIsAdvertising(event.payload);
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // 敏感内容同步前请求确认
   useEffect(() => {
-    const interval = setInterval(async () => {
+    const unlisten = listen<SensitiveSyncRequest>("sensitive-sync-confirmation", async (event) => {
+      const approve = window.confirm("剪贴板内容疑似敏感信息，是否同步到已连接设备？");
       try {
-        const text = await invoke<string>("get_clipboard_text");
-        if (text !== clipboardText && connectedDevice) {
-          setClipboardText(text);
-          // 发送剪贴板更新到连接的设备
-          await invoke("send_clipboard_content", { content: text });
-        }
+        await invoke("confirm_sensitive_sync", { id: event.payload.id, approve });
       } catch (error) {
-        console.error("Failed to get clipboard text:", error);
+        console.error("Failed to confirm sensitive sync:", error);
       }
-    }, 1000);
+    });
 
-    return () => clearInterval(interval);
-  }, [clipboardText, connectedDevice]);
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
 
   async function getClipboardText() {
     try {
@@ -43,7 +114,7 @@ function App() {
       setClipboardText(text as string);
     } catch (error) {
       console.error("Failed to get clipboard text:", error);
-      setClipboardText(`Error: ${error}`);
+      setClipboardText(`Error: ${errorMessage(error)}`);
     }
   }
 
@@ -58,7 +129,7 @@ function App() {
       }
     } catch (error) {
       console.error("Failed to scan BLE devices:", error);
-      setScanError(`${error}`);
+      setScanError(errorMessage(error));
       setBleDevices([]);
     } finally {
       setScanning(false);
@@ -67,14 +138,12 @@ function App() {
 
   const startAdvertising = async () => {
     try {
-      await invoke("start_ble_advertising", {
-        name: "Pasto",
-        serviceUuid: "12345678-1234-1234-1234-1234567890AB",
-      });
+      // 设备名和服务 UUID 由后端按设置决定
+      await invoke("start_ble_advertising");
       setIsAdvertising(true);
       showPopup("已开始广播剪贴板服务");
-    } catch (e: any) {
-      showPopup(`启动广播失败: ${e}`);
+    } catch (e) {
+      showPopup(`启动广播失败: ${errorMessage(e)}`);
     }
   };
 
@@ -83,18 +152,32 @@ function App() {
       await invoke("stop_ble_advertising");
       setIsAdvertising(false);
       showPopup("已停止广播剪贴板服务");
-    } catch (e: any) {
-      showPopup(`停止广播失败: ${e}`);
+    } catch (e) {
+      showPopup(`停止广播失败: ${errorMessage(e)}`);
     }
   };
 
   const connectToDevice = async (address: string, name?: string) => {
     try {
-      await invoke("connect_to_device", { address });
+      await invoke("connect_to_device", { address, name });
       setConnectedDevice(address);
       showPopup(`已连接到设备: ${name || address}`);
-    } catch (e: any) {
-      showPopup(`连接失败: ${e}`);
+    } catch (e) {
+      showPopup(`连接失败: ${errorMessage(e)}`);
+    }
+  };
+
+  const exportDiagnostics = async () => {
+    try {
+      const path = await save({
+        defaultPath: `pasto-diagnostics-${new Date().toISOString().slice(0, 10)}.zip`,
+        filters: [{ name: "Zip", extensions: ["zip"] }],
+      });
+      if (!path) return;
+      await invoke("export_diagnostics", { path });
+      showPopup(`诊断信息已导出到: ${path}`);
+    } catch (e) {
+      showPopup(`导出诊断信息失败: ${errorMessage(e)}`);
     }
   };
 
@@ -111,6 +194,9 @@ function App() {
         >
           {scanning ? "扫描中..." : "扫描设备"}
         </button>
+        <button onClick={exportDiagnostics} style={{ marginLeft: "1rem" }}>
+          导出诊断信息
+        </button>
       </div>
 
       <div style={{ marginTop: 16 }}>